@@ -0,0 +1,133 @@
+// A bitboard is a set of squares packed into a u64, where the square (x, y)
+// is bit `y * COLS + x`; so bit 0 is the top left corner of the board as the
+// frontend draws it, and bit 63 the bottom right one
+pub type Bitboard = u64;
+
+pub const ROWS: usize = 8;
+pub const COLS: usize = 8;
+
+// Offsets of the squares a knight or a king can reach, and the directions in
+// which the sliding figures move
+pub const KNIGHT_OFFSETS: [(i32, i32); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+pub const KING_OFFSETS: [(i32, i32); 8] = [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)];
+pub const ROOK_DIRECTIONS: [(i32, i32); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+pub const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+
+// Attacked squares of the figures whose moves don't depend on other figures,
+// for every square they can stand on; pawns are indexed by player as well,
+// since white pawns attack upwards and black ones downwards
+pub const KNIGHT_ATTACKS: [Bitboard; 64] = leaper_attacks(&KNIGHT_OFFSETS);
+pub const KING_ATTACKS: [Bitboard; 64] = leaper_attacks(&KING_OFFSETS);
+pub const PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    leaper_attacks(&[(-1, -1), (1, -1)]),
+    leaper_attacks(&[(-1, 1), (1, 1)])
+];
+
+// Squares of the same colour as the top left corner
+pub const LIGHT_SQUARES: Bitboard = 0xaa55aa55aa55aa55;
+
+pub fn column(x: usize) -> Bitboard {
+    return 0x0101010101010101 << x
+}
+
+pub fn rows_above(y: usize) -> Bitboard {
+    // Every square of the rows with a smaller y than the given one
+    if y >= ROWS {
+        return !0
+    }
+    return (1 << (y * COLS)) - 1
+}
+
+const fn leaper_attacks(offsets: &[(i32, i32)]) -> [Bitboard; 64] {
+    let mut attacks = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        let x = (square % COLS) as i32;
+        let y = (square / COLS) as i32;
+        let mut i = 0;
+        while i < offsets.len() {
+            let new_x = x + offsets[i].0;
+            let new_y = y + offsets[i].1;
+            if new_x >= 0 && new_x < COLS as i32 && new_y >= 0 && new_y < ROWS as i32 {
+                attacks[square] |= 1 << (new_y as usize * COLS + new_x as usize);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    return attacks
+}
+
+pub fn square_index(square: (usize, usize)) -> usize {
+    return square.1 * COLS + square.0
+}
+
+pub fn square_coords(index: usize) -> (usize, usize) {
+    return (index % COLS, index / COLS)
+}
+
+pub fn square_name(square: (usize, usize)) -> String {
+    // Algebraic name of the square, with files a to h going left to right,
+    // and ranks 8 to 1 going from the top of the board down
+    let (x, y) = square;
+    return format!("{}{}", (b'a' + x as u8) as char, ROWS - y)
+}
+
+pub fn parse_square(name: &str) -> Option<(usize, usize)> {
+    // The inverse of `square_name`
+    let mut chars = name.chars();
+    let file = chars.next()?;
+    let rank = chars.next()?.to_digit(10)? as usize;
+    if chars.next().is_some() || !('a'..='h').contains(&file) || !(1..=ROWS).contains(&rank) {
+        return None
+    }
+    return Some((file as usize - 'a' as usize, ROWS - rank))
+}
+
+pub fn bit(square: (usize, usize)) -> Bitboard {
+    return 1 << square_index(square)
+}
+
+pub fn squares(bitboard: Bitboard) -> Squares {
+    return Squares(bitboard)
+}
+
+// Iterates over the indices of the squares in a bitboard, lowest first
+pub struct Squares(Bitboard);
+
+impl Iterator for Squares {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == 0 {
+            return None
+        }
+        let square = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        return Some(square)
+    }
+}
+
+pub fn sliding_attacks(square: usize, occupied: Bitboard, directions: &[(i32, i32); 4]) -> Bitboard {
+    // Follows every ray from the square until it leaves the board or hits a
+    // figure, which is attacked as well, whoever it belongs to. Too slow for
+    // move generation, it's only used to fill in the magic bitboard tables
+    let mut attacks = 0;
+    for direction in directions.iter() {
+        let (mut x, mut y) = (square % COLS, square / COLS);
+        loop {
+            let new_x = x as i32 + direction.0;
+            let new_y = y as i32 + direction.1;
+            if new_x < 0 || new_y < 0 || new_x >= COLS as i32 || new_y >= ROWS as i32 {
+                break;
+            }
+            x = new_x as usize;
+            y = new_y as usize;
+            attacks |= bit((x, y));
+            if occupied & bit((x, y)) != 0 {
+                break;
+            }
+        }
+    }
+    return attacks
+}
@@ -0,0 +1,782 @@
+use std::collections::HashMap;
+
+use crate::bitboard::{Bitboard, COLS, LIGHT_SQUARES, ROWS, bit, square_coords, square_index, square_name, squares};
+use crate::movegen::{Move, attackers_to};
+use crate::zobrist;
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub enum PlayerName {
+    WHITE,
+    BLACK,
+    NEITHER
+}
+
+impl PlayerName {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlayerName::WHITE => "WHITE",
+            PlayerName::BLACK => "BLACK",
+            PlayerName::NEITHER => "NEITHER"
+        }
+    }
+
+    pub fn opponent(&self) -> PlayerName {
+        match self {
+            PlayerName::WHITE => PlayerName::BLACK,
+            PlayerName::BLACK => PlayerName::WHITE,
+            PlayerName::NEITHER => PlayerName::NEITHER
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        // Position of the player's bitboards on the board
+        match self {
+            PlayerName::WHITE => 0,
+            PlayerName::BLACK => 1,
+            PlayerName::NEITHER => panic!("Nobody has figures of their own")
+        }
+    }
+}
+
+pub const PLAYERS: [PlayerName; 2] = [PlayerName::WHITE, PlayerName::BLACK];
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub enum SquareContent {
+    PAWN,
+    KNIGHT,
+    BISHOP,
+    ROOK,
+    QUEEN,
+    KING,
+    EMPTY
+}
+
+impl SquareContent {
+    pub fn name(&self) -> &'static str {
+        // The same names the frontend uses to describe the figures
+        match self {
+            SquareContent::PAWN => "PAWN",
+            SquareContent::KNIGHT => "KNIGHT",
+            SquareContent::BISHOP => "BISHOP",
+            SquareContent::ROOK => "ROOK",
+            SquareContent::QUEEN => "QUEEN",
+            SquareContent::KING => "KING",
+            SquareContent::EMPTY => "EMPTY"
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        // Position of the figure's bitboard among the bitboards of a player
+        match self {
+            SquareContent::PAWN => 0,
+            SquareContent::KNIGHT => 1,
+            SquareContent::BISHOP => 2,
+            SquareContent::ROOK => 3,
+            SquareContent::QUEEN => 4,
+            SquareContent::KING => 5,
+            SquareContent::EMPTY => panic!("Empty squares have no bitboard")
+        }
+    }
+
+    pub fn letter(&self) -> char {
+        // The letter standing for the figure in FEN and algebraic notation,
+        // in uppercase; FEN uses lowercase letters for black's figures
+        match self {
+            SquareContent::PAWN => 'P',
+            SquareContent::KNIGHT => 'N',
+            SquareContent::BISHOP => 'B',
+            SquareContent::ROOK => 'R',
+            SquareContent::QUEEN => 'Q',
+            SquareContent::KING => 'K',
+            SquareContent::EMPTY => panic!("Empty squares have no letter")
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<SquareContent> {
+        // The inverse of `SquareContent::letter`, in either case
+        match letter.to_ascii_uppercase() {
+            'P' => Some(SquareContent::PAWN),
+            'N' => Some(SquareContent::KNIGHT),
+            'B' => Some(SquareContent::BISHOP),
+            'R' => Some(SquareContent::ROOK),
+            'Q' => Some(SquareContent::QUEEN),
+            'K' => Some(SquareContent::KING),
+            _ => None
+        }
+    }
+}
+
+pub const FIGURES: [SquareContent; 6] = [
+    SquareContent::PAWN,
+    SquareContent::KNIGHT,
+    SquareContent::BISHOP,
+    SquareContent::ROOK,
+    SquareContent::QUEEN,
+    SquareContent::KING
+];
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone)]
+pub struct CastlingRights {
+    pub white_king_side: bool,
+    pub white_queen_side: bool,
+    pub black_king_side: bool,
+    pub black_queen_side: bool
+}
+
+impl CastlingRights {
+    pub fn new(castling_rights: &str) -> Self {
+        return CastlingRights::parse(castling_rights)
+            .unwrap_or_else(|| panic!("Invalid castling rights {:?}", castling_rights))
+    }
+
+    pub fn parse(castling_rights: &str) -> Option<Self> {
+        // Castling rights are described the same way as in FEN, e.g. "KQkq"
+        // when both players can still castle to both sides, or "-" when
+        // nobody can castle anymore
+        let mut rights = CastlingRights::none();
+        for right in castling_rights.chars() {
+            match right {
+                'K' => rights.white_king_side = true,
+                'Q' => rights.white_queen_side = true,
+                'k' => rights.black_king_side = true,
+                'q' => rights.black_queen_side = true,
+                '-' => (),
+                _ => return None
+            }
+        }
+        return Some(rights)
+    }
+
+    pub fn none() -> Self {
+        return CastlingRights {
+            white_king_side: false,
+            white_queen_side: false,
+            black_king_side: false,
+            black_queen_side: false
+        }
+    }
+
+    pub fn description(&self) -> String {
+        // The inverse of `CastlingRights::new`
+        let mut description = String::new();
+        if self.white_king_side {
+            description.push('K');
+        }
+        if self.white_queen_side {
+            description.push('Q');
+        }
+        if self.black_king_side {
+            description.push('k');
+        }
+        if self.black_queen_side {
+            description.push('q');
+        }
+        if description.is_empty() {
+            description.push('-');
+        }
+        return description
+    }
+
+    pub(crate) fn bits(&self) -> usize {
+        // The rights packed into four bits, one for every right
+        return self.white_king_side as usize
+            | (self.white_queen_side as usize) << 1
+            | (self.black_king_side as usize) << 2
+            | (self.black_queen_side as usize) << 3
+    }
+
+    fn update(&mut self, square: (usize, usize)) {
+        // Moving the king or a rook away from its starting square, or
+        // capturing a rook on its starting square, loses the related rights
+        match square {
+            (4, 7) => { self.white_king_side = false; self.white_queen_side = false },
+            (7, 7) => self.white_king_side = false,
+            (0, 7) => self.white_queen_side = false,
+            (4, 0) => { self.black_king_side = false; self.black_queen_side = false },
+            (7, 0) => self.black_king_side = false,
+            (0, 0) => self.black_queen_side = false,
+            _ => ()
+        }
+    }
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum GameStatus {
+    ONGOING,
+    // Holds the winner
+    CHECKMATE(PlayerName),
+    STALEMATE,
+    DRAW(DrawReason)
+}
+
+#[allow(non_camel_case_types)]
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum DrawReason {
+    // No capture or pawn move in the last fifty moves of each player
+    FIFTY_MOVE_RULE,
+    THREEFOLD_REPETITION,
+    FIVEFOLD_REPETITION,
+    // Neither player has enough figures left to checkmate
+    INSUFFICIENT_MATERIAL
+}
+
+impl DrawReason {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DrawReason::FIFTY_MOVE_RULE => "FIFTY_MOVE_RULE",
+            DrawReason::THREEFOLD_REPETITION => "THREEFOLD_REPETITION",
+            DrawReason::FIVEFOLD_REPETITION => "FIVEFOLD_REPETITION",
+            DrawReason::INSUFFICIENT_MATERIAL => "INSUFFICIENT_MATERIAL"
+        }
+    }
+}
+
+// Everything about a position which can't be read off the squares
+#[derive(PartialEq, Copy, Clone)]
+pub struct GameState {
+    pub side_to_move: PlayerName,
+    pub castling_rights: CastlingRights,
+
+    // The square a pawn skipped over with its double move on the previous
+    // turn, if any; only a pawn capturing onto this square takes en passant
+    pub en_passant: Option<(usize, usize)>,
+
+    // Number of moves since the last capture or pawn move, and the number of
+    // the current move, which starts at 1 and goes up after black moves
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32
+}
+
+impl GameState {
+    pub fn new() -> Self {
+        // The state at the start of a game
+        return GameState {
+            side_to_move: PlayerName::WHITE,
+            castling_rights: CastlingRights::new("KQkq"),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1
+        }
+    }
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        return GameState::new()
+    }
+}
+
+#[derive(Clone)]
+pub struct Board {
+    // One bitboard for every kind of figure of every player, indexed by
+    // `PlayerName::index` and `SquareContent::index`, and the squares taken
+    // by any figure of each player
+    pub(crate) pieces: [[Bitboard; 6]; 2],
+    pub(crate) occupied: [Bitboard; 2],
+
+    // Read it freely, but change it only through `Board::set_state`, so
+    // that the key of the position stays up to date
+    pub state: GameState,
+
+    // Zobrist key of the current position, see `zobrist.rs`
+    key: u64,
+
+    // Every move made on the board, with whatever is needed to take it back
+    history: Vec<MoveRecord>,
+
+    // Keys of all the positions before the current one, oldest first; these
+    // can reach further back than the moves in `history`, since the earlier
+    // moves of a game aren't replayed on the board
+    pub(crate) position_history: Vec<u64>
+}
+
+// The first and last rows, where pawns never stand
+const BACK_ROWS: Bitboard = 0xff000000000000ff;
+
+#[derive(Copy, Clone)]
+struct MoveRecord {
+    mv: Move,
+    // Always belongs to the opponent of whoever made the move
+    captured: SquareContent,
+    state: GameState
+}
+
+impl Board {
+    pub fn new(board_description: &[HashMap<String, String>]) -> Result<Self, String> {
+        // Reads the squares the way the frontend describes them, which may
+        // come from anywhere, so nothing in them is taken for granted
+        let mut board = Board::empty();
+        let mut described: Bitboard = 0;
+        for square in board_description.iter() {
+            let field = |name: &str| square.get(name).ok_or_else(|| format!("Missing {:?} in square {:?}", name, square));
+            let x = field("x")?.parse::<usize>().ok().filter(|&x| x < COLS)
+                .ok_or_else(|| format!("Invalid column in square {:?}", square))?;
+            let y = field("y")?.parse::<usize>().ok().filter(|&y| y < ROWS)
+                .ok_or_else(|| format!("Invalid row in square {:?}", square))?;
+            let content = match field("figure")?.as_str() {
+                "PAWN" => SquareContent::PAWN,
+                "KNIGHT" => SquareContent::KNIGHT,
+                "BISHOP" => SquareContent::BISHOP,
+                "ROOK" => SquareContent::ROOK,
+                "QUEEN" => SquareContent::QUEEN,
+                "KING" => SquareContent::KING,
+                "EMPTY" => SquareContent::EMPTY,
+                x => return Err(format!("Invalid figure type {:?}", x))
+            };
+            let belongs_to = match field("belongs_to")?.as_str() {
+                "WHITE" => PlayerName::WHITE,
+                "BLACK" => PlayerName::BLACK,
+                "NEITHER" => PlayerName::NEITHER,
+                x => return Err(format!("Invalid player type {:?}", x))
+            };
+            // Two figures on one square would each keep their bit set
+            if described & bit((x, y)) != 0 {
+                return Err(format!("Square {} is described more than once", square_name((x, y))))
+            }
+            described |= bit((x, y));
+            if content != SquareContent::EMPTY && belongs_to != PlayerName::NEITHER {
+                board.put(square_index((x, y)), content, belongs_to);
+            }
+        }
+        board.validate()?;
+        return Ok(board)
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        // Rejects the positions the move generator can't handle: without
+        // exactly one king per side, or with pawns on the first or last row,
+        // from where they have nowhere to move
+        for player in PLAYERS.iter() {
+            if self.pieces[player.index()][SquareContent::KING.index()].count_ones() != 1 {
+                return Err(format!("{} needs exactly one king", player.name()))
+            }
+            if self.pieces[player.index()][SquareContent::PAWN.index()] & BACK_ROWS != 0 {
+                return Err("pawns can't stand on the first or last row".to_string())
+            }
+        }
+        return Ok(())
+    }
+
+    pub fn description(&self) -> Vec<HashMap<String, String>> {
+        // The inverse of `Board::new`, describing every square the same way
+        // the frontend does
+        let mut description = Vec::new();
+        for y in 0..ROWS {
+            for x in 0..COLS {
+                let (content, player) = self.figure_at((x, y));
+                let mut square = HashMap::new();
+                square.insert("x".to_string(), x.to_string());
+                square.insert("y".to_string(), y.to_string());
+                square.insert("figure".to_string(), content.name().to_string());
+                square.insert("belongs_to".to_string(), player.name().to_string());
+                description.push(square);
+            }
+        }
+        return description
+    }
+
+    pub(crate) fn empty() -> Self {
+        let state = GameState { castling_rights: CastlingRights::none(), ..GameState::new() };
+        return Board {
+            pieces: [[0; 6]; 2],
+            occupied: [0; 2],
+            state,
+            key: zobrist::state_key(&state, 0),
+            history: Vec::new(),
+            position_history: Vec::new()
+        }
+    }
+
+    pub fn figure_at(&self, square: (usize, usize)) -> (SquareContent, PlayerName) {
+        // What stands on the square, and whose it is
+        let bit = bit(square);
+        for player in PLAYERS.iter() {
+            if self.occupied[player.index()] & bit == 0 {
+                continue;
+            }
+            for figure in FIGURES.iter() {
+                if self.pieces[player.index()][figure.index()] & bit != 0 {
+                    return (*figure, *player)
+                }
+            }
+        }
+        return (SquareContent::EMPTY, PlayerName::NEITHER)
+    }
+
+    pub fn set_state(&mut self, state: GameState) {
+        self.key ^= self.state_key(&self.state) ^ self.state_key(&state);
+        self.state = state;
+    }
+
+    fn state_key(&self, state: &GameState) -> u64 {
+        // Whether the en passant square counts towards the key depends on the
+        // pawns of the side to move, so this has to be worked out with the
+        // figures standing where they do in that state
+        return zobrist::state_key(state, self.pieces[state.side_to_move.index()][SquareContent::PAWN.index()])
+    }
+
+    pub(crate) fn all_occupied(&self) -> Bitboard {
+        return self.occupied[0] | self.occupied[1]
+    }
+
+    pub(crate) fn put(&mut self, square: usize, content: SquareContent, player: PlayerName) {
+        self.pieces[player.index()][content.index()] |= 1 << square;
+        self.occupied[player.index()] |= 1 << square;
+        self.key ^= zobrist::PIECES[player.index()][content.index()][square];
+    }
+
+    pub(crate) fn remove(&mut self, square: usize, content: SquareContent, player: PlayerName) {
+        self.pieces[player.index()][content.index()] &= !(1 << square);
+        self.occupied[player.index()] &= !(1 << square);
+        self.key ^= zobrist::PIECES[player.index()][content.index()][square];
+    }
+
+    pub fn is_in_check(&self, player: PlayerName) -> bool {
+        // Without a king there's nothing to be in check
+        match self.find_king(player) {
+            Some(king) => self.is_square_attacked(king, player.opponent()),
+            None => false
+        }
+    }
+
+    pub fn is_square_attacked(&self, square: (usize, usize), by: PlayerName) -> bool {
+        return attackers_to(&self.pieces, self.all_occupied(), square_index(square), by) != 0
+    }
+
+    pub fn attackers_of(&self, square: (usize, usize), by: PlayerName) -> Vec<(usize, usize)> {
+        // The squares of all of the player's figures attacking the square
+        let attackers = attackers_to(&self.pieces, self.all_occupied(), square_index(square), by);
+        return squares(attackers).map(square_coords).collect()
+    }
+
+    pub(crate) fn find_king(&self, player: PlayerName) -> Option<(usize, usize)> {
+        return squares(self.pieces[player.index()][SquareContent::KING.index()]).next().map(square_coords)
+    }
+
+    pub fn status(&self) -> GameStatus {
+        // The game ends when the side to move has no legal moves left, which
+        // is checkmate if its king is attacked and stalemate otherwise
+        let player = self.state.side_to_move;
+        if self.legal_moves().is_empty() {
+            if self.is_in_check(player) {
+                return GameStatus::CHECKMATE(player.opponent())
+            }
+            return GameStatus::STALEMATE
+        }
+
+        if self.has_insufficient_material() {
+            return GameStatus::DRAW(DrawReason::INSUFFICIENT_MATERIAL)
+        }
+        let repetitions = self.repetitions();
+        if repetitions >= 5 {
+            return GameStatus::DRAW(DrawReason::FIVEFOLD_REPETITION)
+        }
+        if repetitions >= 3 {
+            return GameStatus::DRAW(DrawReason::THREEFOLD_REPETITION)
+        }
+        if self.state.halfmove_clock >= 100 {
+            return GameStatus::DRAW(DrawReason::FIFTY_MOVE_RULE)
+        }
+        return GameStatus::ONGOING
+    }
+
+    pub fn position_key(&self) -> u64 {
+        // Two positions are the same if the same figures stand on the same
+        // squares, and the same moves are available in them, which is why
+        // the side to move, castling rights and en passant square count too
+        return self.key
+    }
+
+    pub fn recent_positions(&self) -> &[u64] {
+        // A capture or pawn move can never be undone, so no position from
+        // before the last one can come up again
+        let count = (self.state.halfmove_clock as usize).min(self.position_history.len());
+        return &self.position_history[self.position_history.len() - count..]
+    }
+
+    pub(crate) fn repetitions(&self) -> usize {
+        // How many times the current position has occurred, including now
+        let key = self.position_key();
+        return 1 + self.recent_positions().iter().filter(|previous| **previous == key).count()
+    }
+
+    pub(crate) fn has_insufficient_material(&self) -> bool {
+        // Checkmate is impossible with only the kings left, a king and a
+        // single minor figure against a lone king, or any number of bishops
+        // which all move on squares of the same colour
+        let figures = |content: SquareContent| self.pieces[0][content.index()] | self.pieces[1][content.index()];
+        if figures(SquareContent::PAWN) | figures(SquareContent::ROOK) | figures(SquareContent::QUEEN) != 0 {
+            return false
+        }
+
+        let knights = figures(SquareContent::KNIGHT).count_ones();
+        let bishops = figures(SquareContent::BISHOP);
+        if knights == 0 {
+            return bishops & LIGHT_SQUARES == 0 || bishops & !LIGHT_SQUARES == 0
+        }
+        return knights == 1 && bishops == 0
+    }
+
+    pub fn make_move(&mut self, mv: &Move) {
+        // Moves the figure on `from` to `to`, capturing whatever is there, and
+        // takes care of the side effects of castling and pawn moves
+        let (from_x, from_y) = mv.from;
+        let (to_x, to_y) = mv.to;
+        let (moving, player) = self.figure_at(mv.from);
+        let from = square_index(mv.from);
+        let to = square_index(mv.to);
+
+        // The pawn taken en passant isn't standing on the target square, but
+        // beside the capturing pawn
+        let captured_square = if mv.is_en_passant() { square_index((to_x, from_y)) } else { to };
+        let (captured, _) = self.figure_at(square_coords(captured_square));
+
+        // Remember everything the move overwrites, so it can be taken back
+        self.position_history.push(self.position_key());
+        self.history.push(MoveRecord { mv: *mv, captured, state: self.state });
+        self.key ^= self.state_key(&self.state);
+
+        if captured != SquareContent::EMPTY {
+            self.remove(captured_square, captured, player.opponent());
+        }
+        self.remove(from, moving, player);
+        self.put(to, mv.promotion.unwrap_or(moving), player);
+
+        // When castling, the rook jumps over to the other side of the king
+        if mv.is_castling() {
+            let (rook_from_x, rook_to_x) = if to_x > from_x { (COLS - 1, to_x - 1) } else { (0, to_x + 1) };
+            self.remove(square_index((rook_from_x, from_y)), SquareContent::ROOK, player);
+            self.put(square_index((rook_to_x, from_y)), SquareContent::ROOK, player);
+        }
+        self.state.castling_rights.update(mv.from);
+        self.state.castling_rights.update(mv.to);
+
+        // The en passant square only lives for a single turn, and is set again
+        // only when a pawn makes its double move
+        if mv.is_double_pawn_push() {
+            self.state.en_passant = Some((from_x, (from_y + to_y) / 2));
+        } else {
+            self.state.en_passant = None;
+        }
+
+        // The halfmove clock counts the moves since the last capture or pawn
+        // move, and the fullmove number goes up once black has moved
+        if mv.is_capture() || moving == SquareContent::PAWN {
+            self.state.halfmove_clock = 0;
+        } else {
+            self.state.halfmove_clock += 1;
+        }
+        if self.state.side_to_move == PlayerName::BLACK {
+            self.state.fullmove_number += 1;
+        }
+        self.state.side_to_move = self.state.side_to_move.opponent();
+        self.key ^= self.state_key(&self.state);
+    }
+
+    pub fn unmake_move(&mut self) -> Option<Move> {
+        // Takes back the last move made on the board, restoring exactly the
+        // state from before it
+        let record = self.history.pop()?;
+        self.position_history.pop();
+        let mv = record.mv;
+        let (from_x, from_y) = mv.from;
+        let (to_x, _) = mv.to;
+        let (moved, player) = self.figure_at(mv.to);
+        let from = square_index(mv.from);
+        let to = square_index(mv.to);
+        self.key ^= self.state_key(&self.state);
+
+        // A promoted figure turns back into the pawn it was
+        self.remove(to, moved, player);
+        self.put(from, if mv.promotion.is_some() { SquareContent::PAWN } else { moved }, player);
+
+        if record.captured != SquareContent::EMPTY {
+            let captured_square = if mv.is_en_passant() { square_index((to_x, from_y)) } else { to };
+            self.put(captured_square, record.captured, player.opponent());
+        }
+
+        if mv.is_castling() {
+            let (rook_from_x, rook_to_x) = if to_x > from_x { (COLS - 1, to_x - 1) } else { (0, to_x + 1) };
+            self.remove(square_index((rook_to_x, from_y)), SquareContent::ROOK, player);
+            self.put(square_index((rook_from_x, from_y)), SquareContent::ROOK, player);
+        }
+
+        self.state = record.state;
+        self.key ^= self.state_key(&self.state);
+
+        return Some(mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Board, DrawReason, GameStatus, PlayerName};
+    use crate::bitboard::parse_square;
+
+    fn play(board: &mut Board, moves: &[&str]) {
+        for uci in moves.iter() {
+            let mv = board.parse_uci_move(uci).unwrap();
+            board.make_move(&mv);
+        }
+    }
+
+    #[test]
+    fn fifty_move_rule() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+        assert_eq!(board.status(), GameStatus::ONGOING);
+        let mut board = board;
+        play(&mut board, &["a1a2"]);
+        assert_eq!(board.status(), GameStatus::DRAW(DrawReason::FIFTY_MOVE_RULE));
+
+        // Checkmate still wins, even on the hundredth halfmove
+        let mut board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80").unwrap();
+        play(&mut board, &["a1a8"]);
+        assert_eq!(board.status(), GameStatus::CHECKMATE(PlayerName::WHITE));
+    }
+
+    #[test]
+    fn repetitions() {
+        let mut board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+        play(&mut board, &shuffle);
+        assert_eq!(board.status(), GameStatus::ONGOING);
+        play(&mut board, &shuffle);
+        assert_eq!(board.status(), GameStatus::DRAW(DrawReason::THREEFOLD_REPETITION));
+        play(&mut board, &shuffle);
+        assert_eq!(board.status(), GameStatus::DRAW(DrawReason::THREEFOLD_REPETITION));
+        play(&mut board, &shuffle);
+        assert_eq!(board.status(), GameStatus::DRAW(DrawReason::FIVEFOLD_REPETITION));
+
+        // Only the positions since the last capture or pawn move can repeat
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/P7/R3K3 w - - 0 1").unwrap();
+        play(&mut board, &["a1b1", "e8d8", "b1a1", "d8e8", "a2a3", "e8d8", "a1b1", "d8e8", "b1a1"]);
+        assert_eq!(board.status(), GameStatus::ONGOING);
+        assert_eq!(board.recent_positions().len(), 4);
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let draws = [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1N2K3 w - - 0 1",
+            // Bishops on dark squares only, on both sides
+            "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/B1B1K3 w - - 0 1",
+        ];
+        for fen in draws.iter() {
+            assert_eq!(Board::from_fen(fen).unwrap().status(), GameStatus::DRAW(DrawReason::INSUFFICIENT_MATERIAL), "{}", fen);
+        }
+
+        let ongoing = [
+            // Bishops on squares of both colours, two knights, a knight and a
+            // bishop, and anything with a pawn, rook or queen
+            "2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1",
+            "4k3/p7/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/Q3K3 w - - 0 1",
+        ];
+        for fen in ongoing.iter() {
+            assert_eq!(Board::from_fen(fen).unwrap().status(), GameStatus::ONGOING, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn attacked_squares() {
+        let board = Board::from_fen("4k3/8/8/5n2/3p4/8/6P1/R3K2B w - - 0 1").unwrap();
+        let attacked = |name: &str, by: PlayerName| board.is_square_attacked(parse_square(name).unwrap(), by);
+        let attackers = |name: &str, by: PlayerName| {
+            let mut attackers: Vec<(usize, usize)> = board.attackers_of(parse_square(name).unwrap(), by);
+            attackers.sort();
+            return attackers
+        };
+
+        // Pawns attack the squares diagonally in front of them, even when
+        // there's nothing on them to capture, but not the one straight ahead
+        assert!(attacked("c3", PlayerName::BLACK) && attacked("e3", PlayerName::BLACK));
+        assert!(!attacked("d3", PlayerName::BLACK));
+        assert!(attacked("f3", PlayerName::WHITE) && attacked("h3", PlayerName::WHITE));
+        assert!(!attacked("g3", PlayerName::WHITE));
+
+        // Sliding figures are stopped by the first figure in their way,
+        // whichever side it belongs to
+        assert!(attacked("d1", PlayerName::WHITE) && attacked("a8", PlayerName::WHITE));
+        assert!(!attacked("g1", PlayerName::WHITE) && !attacked("e4", PlayerName::WHITE));
+        assert!(!attacked("h8", PlayerName::WHITE));
+
+        let squares = |names: &[&str]| {
+            let mut squares: Vec<(usize, usize)> = names.iter().map(|name| parse_square(name).unwrap()).collect();
+            squares.sort();
+            return squares
+        };
+        assert_eq!(attackers("e3", PlayerName::BLACK), squares(&["d4", "f5"]));
+        assert_eq!(attackers("d1", PlayerName::WHITE), squares(&["a1", "e1"]));
+        assert_eq!(attackers("e3", PlayerName::WHITE), Vec::new());
+        assert_eq!(attackers("h8", PlayerName::WHITE), Vec::new());
+    }
+
+    #[test]
+    fn descriptions() {
+        let fen = "4k3/8/8/3q4/8/8/4P3/4K3 w - - 0 1";
+        let description = Board::from_fen(fen).unwrap().description();
+        assert_eq!(Board::new(&description).unwrap().to_fen(), fen);
+
+        // Changes the square at (x, y) of the description to the figure
+        let with = |changes: &[((usize, usize), &str, &str)]| {
+            let mut description = description.clone();
+            for ((x, y), figure, player) in changes.iter() {
+                let square = description.iter_mut().find(|square| square["x"] == x.to_string() && square["y"] == y.to_string()).unwrap();
+                square.insert("figure".to_string(), figure.to_string());
+                square.insert("belongs_to".to_string(), player.to_string());
+            }
+            return description
+        };
+        assert!(Board::new(&with(&[((0, 7), "ROOK", "WHITE")])).is_ok());
+        for invalid in [
+            // A pawn on the row it promotes on, and on its own first row
+            with(&[((0, 0), "PAWN", "WHITE")]),
+            with(&[((0, 0), "PAWN", "BLACK")]),
+            // No king, and two kings
+            with(&[((4, 7), "EMPTY", "NEITHER")]),
+            with(&[((0, 0), "KING", "BLACK")]),
+            // Unknown figures and players
+            with(&[((0, 0), "DRAGON", "WHITE")]),
+            with(&[((0, 0), "ROOK", "RED")])
+        ].iter() {
+            assert!(Board::new(invalid).is_err());
+        }
+
+        // A square described twice, and one off the board
+        let mut twice = description.clone();
+        let mut rook = twice[27].clone();
+        rook.insert("figure".to_string(), "ROOK".to_string());
+        rook.insert("belongs_to".to_string(), "BLACK".to_string());
+        twice.push(rook);
+        assert!(Board::new(&twice).is_err());
+        let mut outside = description.clone();
+        outside[0].insert("x".to_string(), "8".to_string());
+        assert!(Board::new(&outside).is_err());
+    }
+
+    #[test]
+    fn make_and_unmake() {
+        // A double push, a capture, en passant, a promotion with a capture
+        // and castling, each of which has to update the key the same way a
+        // fresh board for the position would get it, and be taken back
+        let mut board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let mut positions = Vec::new();
+        for uci in ["e2e4", "d7d5", "e4d5", "e7e5", "d5e6", "a7a6", "e6f7", "e8e7", "f7g8q", "h8g8", "g1f3", "a6a5", "f1c4", "a5a4", "e1g1"].iter() {
+            positions.push((board.to_fen(), board.position_key()));
+            play(&mut board, &[uci]);
+            assert_eq!(board.position_key(), Board::from_fen(&board.to_fen()).unwrap().position_key());
+        }
+        assert_eq!(board.to_fen(), "rnbq1br1/1pp1k1pp/8/8/p1B5/5N2/PPPP1PPP/RNBQ1RK1 b - - 1 8");
+        while let Some((fen, key)) = positions.pop() {
+            board.unmake_move().unwrap();
+            assert_eq!((board.to_fen(), board.position_key()), (fen, key));
+        }
+        assert!(board.unmake_move().is_none());
+    }
+}
@@ -0,0 +1,65 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::board::Board;
+use crate::movegen::Move;
+use crate::random::Xorshift;
+
+// A way of picking the bot's moves
+pub trait Strategy {
+    // A legal move of the side to move, or None if there isn't any. The
+    // board may be used to try moves out, but has to be left as it was
+    fn choose_move(&mut self, board: &mut Board) -> Option<Move>;
+}
+
+// Plays any legal move, all of them being equally likely
+pub struct RandomStrategy {
+    random: Xorshift
+}
+
+impl RandomStrategy {
+    pub fn new(seed: u64) -> Self {
+        return RandomStrategy { random: Xorshift::new(seed) }
+    }
+
+    pub fn from_time() -> Self {
+        // A different game every time
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_nanos() as u64);
+        return RandomStrategy::new(nanos)
+    }
+}
+
+impl Strategy for RandomStrategy {
+    fn choose_move(&mut self, board: &mut Board) -> Option<Move> {
+        let moves = board.legal_moves();
+        if moves.is_empty() {
+            return None
+        }
+        return Some(moves[self.random.below(moves.len())])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RandomStrategy, Strategy};
+    use crate::board::Board;
+
+    #[test]
+    fn random_moves_are_legal() {
+        let mut strategy = RandomStrategy::new(1);
+        let mut board = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        for _ in 0..100 {
+            let mv = match strategy.choose_move(&mut board) {
+                Some(mv) => mv,
+                None => break
+            };
+            assert!(board.legal_moves().contains(&mv));
+            board.make_move(&mv);
+        }
+    }
+
+    #[test]
+    fn no_move_when_the_game_is_over() {
+        let mut board = Board::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 1 1").unwrap();
+        assert_eq!(RandomStrategy::new(1).choose_move(&mut board), None);
+    }
+}
@@ -0,0 +1,614 @@
+use std::ops::{AddAssign, Mul, SubAssign};
+use std::sync::RwLock;
+
+use crate::bitboard::{Bitboard, COLS, KING_ATTACKS, KNIGHT_ATTACKS, LIGHT_SQUARES, PAWN_ATTACKS, column, rows_above, square_coords, squares};
+use crate::board::{Board, FIGURES, PLAYERS, PlayerName, SquareContent};
+use crate::magic::{bishop_attacks, queen_attacks, rook_attacks};
+
+// Worth of every figure in centipawns, indexed by `SquareContent::index`; the
+// king can never be captured, so it doesn't count. The evaluation has its own
+// material weights, which start out as these in the middlegame, and these are
+// what the search uses to guess which captures are worth trying first
+pub const FIGURE_VALUES: [i32; 6] = [100, 320, 330, 500, 900, 0];
+
+impl SquareContent {
+    pub fn value(&self) -> i32 {
+        match self {
+            SquareContent::EMPTY => 0,
+            figure => FIGURE_VALUES[figure.index()]
+        }
+    }
+}
+
+// How much better or worse a figure is on every square than on average in
+// the middlegame, as seen by white, with the squares in the same order as the
+// board's: a8 first and h1 last. Black's figures use the same tables with the
+// rows flipped
+#[rustfmt::skip]
+pub const PIECE_SQUARE_TABLES: [[i32; 64]; 6] = [
+    // Pawns are pushed forward, in the centre more than on the wings, but the
+    // ones in front of a castled king had better stay where they are
+    [
+         0,   0,   0,   0,   0,   0,   0,   0,
+        50,  50,  50,  50,  50,  50,  50,  50,
+        10,  10,  20,  30,  30,  20,  10,  10,
+         5,   5,  10,  25,  25,  10,   5,   5,
+         0,   0,   0,  20,  20,   0,   0,   0,
+         5,  -5, -10,   0,   0, -10,  -5,   5,
+         5,  10,  10, -20, -20,  10,  10,   5,
+         0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    // A knight on the rim is dim
+    [
+       -50, -40, -30, -30, -30, -30, -40, -50,
+       -40, -20,   0,   0,   0,   0, -20, -40,
+       -30,   0,  10,  15,  15,  10,   0, -30,
+       -30,   5,  15,  20,  20,  15,   5, -30,
+       -30,   0,  15,  20,  20,  15,   0, -30,
+       -30,   5,  10,  15,  15,  10,   5, -30,
+       -40, -20,   0,   5,   5,   0, -20, -40,
+       -50, -40, -30, -30, -30, -30, -40, -50,
+    ],
+    // Bishops want long diagonals, away from the corners
+    [
+       -20, -10, -10, -10, -10, -10, -10, -20,
+       -10,   0,   0,   0,   0,   0,   0, -10,
+       -10,   0,   5,  10,  10,   5,   0, -10,
+       -10,   5,   5,  10,  10,   5,   5, -10,
+       -10,   0,  10,  10,  10,  10,   0, -10,
+       -10,  10,  10,  10,  10,  10,  10, -10,
+       -10,   5,   0,   0,   0,   0,   5, -10,
+       -20, -10, -10, -10, -10, -10, -10, -20,
+    ],
+    // Rooks belong on the seventh row and in the centre of the first
+    [
+         0,   0,   0,   0,   0,   0,   0,   0,
+         5,  10,  10,  10,  10,  10,  10,   5,
+        -5,   0,   0,   0,   0,   0,   0,  -5,
+        -5,   0,   0,   0,   0,   0,   0,  -5,
+        -5,   0,   0,   0,   0,   0,   0,  -5,
+        -5,   0,   0,   0,   0,   0,   0,  -5,
+        -5,   0,   0,   0,   0,   0,   0,  -5,
+         0,   0,   0,   5,   5,   0,   0,   0,
+    ],
+    // The queen is a bit better in the centre
+    [
+       -20, -10, -10,  -5,  -5, -10, -10, -20,
+       -10,   0,   0,   0,   0,   0,   0, -10,
+       -10,   0,   5,   5,   5,   5,   0, -10,
+        -5,   0,   5,   5,   5,   5,   0,  -5,
+         0,   0,   5,   5,   5,   5,   0,  -5,
+       -10,   5,   5,   5,   5,   5,   0, -10,
+       -10,   0,   5,   0,   0,   0,   0, -10,
+       -20, -10, -10,  -5,  -5, -10, -10, -20,
+    ],
+    // The king hides behind its pawns, preferably after castling
+    [
+       -30, -40, -40, -50, -50, -40, -40, -30,
+       -30, -40, -40, -50, -50, -40, -40, -30,
+       -30, -40, -40, -50, -50, -40, -40, -30,
+       -30, -40, -40, -50, -50, -40, -40, -30,
+       -20, -30, -30, -40, -40, -30, -30, -20,
+       -10, -20, -20, -20, -20, -20, -20, -10,
+        20,  20,   0,   0,   0,   0,  20,  20,
+        20,  30,  10,   0,   0,  10,  30,  20,
+    ],
+];
+
+// In the endgame there's no more need to hide the king, which joins the
+// fight in the centre, and the pawns race towards promotion. The other
+// figures use the same tables as in the middlegame
+#[rustfmt::skip]
+pub const ENDGAME_KING_TABLE: [i32; 64] = [
+   -50, -40, -30, -20, -20, -30, -40, -50,
+   -30, -20, -10,   0,   0, -10, -20, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -30,   0,   0,   0,   0, -30, -30,
+   -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+#[rustfmt::skip]
+pub const ENDGAME_PAWN_TABLE: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    80,  80,  80,  80,  80,  80,  80,  80,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    30,  30,  30,  30,  30,  30,  30,  30,
+    20,  20,  20,  20,  20,  20,  20,  20,
+    10,  10,  10,  10,  10,  10,  10,  10,
+     0,   0,   0,   0,   0,   0,   0,   0,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+// How much every figure counts towards the game still being in the
+// middlegame; with all of them on the board, the phase is at its maximum
+const PHASE_WEIGHTS: [i32; 6] = [0, 1, 1, 2, 4, 0];
+const MAX_PHASE: i32 = 24;
+
+// A score for the middlegame and one for the endgame. The evaluation mixes
+// the two depending on how much material is left, so that the figures change
+// their plans smoothly as the game goes on
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Score {
+    pub middlegame: i32,
+    pub endgame: i32
+}
+
+impl Score {
+    pub const fn new(middlegame: i32, endgame: i32) -> Self {
+        return Score { middlegame, endgame }
+    }
+
+    pub fn taper(&self, phase: i32) -> i32 {
+        let phase = phase.min(MAX_PHASE);
+        return (self.middlegame * phase + self.endgame * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl AddAssign for Score {
+    fn add_assign(&mut self, other: Score) {
+        self.middlegame += other.middlegame;
+        self.endgame += other.endgame;
+    }
+}
+
+impl SubAssign for Score {
+    fn sub_assign(&mut self, other: Score) {
+        self.middlegame -= other.middlegame;
+        self.endgame -= other.endgame;
+    }
+}
+
+impl Mul<i32> for Score {
+    type Output = Score;
+
+    fn mul(self, times: i32) -> Score {
+        return Score::new(self.middlegame * times, self.endgame * times)
+    }
+}
+
+// Everything the evaluation weighs, which can be changed while the program
+// runs to tune the bot
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Weights {
+    // Indexed by `SquareContent::index`
+    pub material: [Score; 6],
+    // For every pawn on a file beyond the first
+    pub doubled_pawn: Score,
+    // A pawn without pawns of its own on the files next to it
+    pub isolated_pawn: Score,
+    // A pawn which its neighbours have left behind, and which can't move up
+    // to them without being taken by a pawn
+    pub backward_pawn: Score,
+    // A pawn which no pawn can stop anymore, indexed by its rank as seen by
+    // its own side, from 0 to 7
+    pub passed_pawn: [Score; 8],
+    // Bishops on squares of both colours
+    pub bishop_pair: Score,
+    // A rook on a file without pawns, or only the opponent's
+    pub rook_on_open_file: Score,
+    pub rook_on_half_open_file: Score,
+    // For every pawn of its own in the two rows in front of the king, on its
+    // file or the ones next to it
+    pub pawn_shield: Score,
+    // For every square a figure attacks which isn't taken by a figure of its
+    // own or attacked by a pawn of the opponent, indexed by
+    // `SquareContent::index`; pawns and kings don't count
+    pub mobility: [Score; 6],
+    // For every square around the opponent's king a figure attacks, indexed
+    // by `SquareContent::index`; pawns and kings don't count
+    pub king_attack: [Score; 6]
+}
+
+impl Weights {
+    // Names of the weights which are a single score, as Python sees them
+    pub const TERMS: [&'static str; 7] = [
+        "doubled_pawn", "isolated_pawn", "backward_pawn", "bishop_pair", "rook_on_open_file", "rook_on_half_open_file", "pawn_shield"
+    ];
+
+    // Names of the weights with a score for every figure
+    pub const FIGURE_TERMS: [&'static str; 3] = ["material", "mobility", "king_attack"];
+
+    pub fn term(&mut self, name: &str) -> Option<&mut Score> {
+        match name {
+            "doubled_pawn" => Some(&mut self.doubled_pawn),
+            "isolated_pawn" => Some(&mut self.isolated_pawn),
+            "backward_pawn" => Some(&mut self.backward_pawn),
+            "bishop_pair" => Some(&mut self.bishop_pair),
+            "rook_on_open_file" => Some(&mut self.rook_on_open_file),
+            "rook_on_half_open_file" => Some(&mut self.rook_on_half_open_file),
+            "pawn_shield" => Some(&mut self.pawn_shield),
+            _ => None
+        }
+    }
+
+    pub fn figure_term(&mut self, name: &str) -> Option<&mut [Score; 6]> {
+        match name {
+            "material" => Some(&mut self.material),
+            "mobility" => Some(&mut self.mobility),
+            "king_attack" => Some(&mut self.king_attack),
+            _ => None
+        }
+    }
+}
+
+pub const DEFAULT_WEIGHTS: Weights = Weights {
+    material: [
+        Score::new(FIGURE_VALUES[0], 120),
+        Score::new(FIGURE_VALUES[1], 300),
+        Score::new(FIGURE_VALUES[2], 320),
+        Score::new(FIGURE_VALUES[3], 520),
+        Score::new(FIGURE_VALUES[4], 920),
+        Score::new(FIGURE_VALUES[5], 0)
+    ],
+    doubled_pawn: Score::new(-10, -20),
+    isolated_pawn: Score::new(-10, -15),
+    backward_pawn: Score::new(-8, -10),
+    passed_pawn: [
+        Score::new(0, 0),
+        Score::new(5, 10),
+        Score::new(10, 20),
+        Score::new(15, 35),
+        Score::new(25, 60),
+        Score::new(40, 100),
+        Score::new(60, 150),
+        Score::new(0, 0)
+    ],
+    bishop_pair: Score::new(30, 50),
+    rook_on_open_file: Score::new(25, 10),
+    rook_on_half_open_file: Score::new(10, 5),
+    pawn_shield: Score::new(12, 0),
+    mobility: [
+        Score::new(0, 0),
+        Score::new(4, 4),
+        Score::new(5, 5),
+        Score::new(2, 4),
+        Score::new(1, 2),
+        Score::new(0, 0)
+    ],
+    king_attack: [
+        Score::new(0, 0),
+        Score::new(8, 2),
+        Score::new(6, 2),
+        Score::new(8, 3),
+        Score::new(12, 4),
+        Score::new(0, 0)
+    ]
+};
+
+static WEIGHTS: RwLock<Weights> = RwLock::new(DEFAULT_WEIGHTS);
+
+pub fn weights() -> Weights {
+    // The weights can't be left half written, so a panic while they were
+    // locked doesn't matter
+    return *WEIGHTS.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub fn set_weights(weights: Weights) {
+    *WEIGHTS.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = weights;
+}
+
+pub fn piece_square_score(figure: SquareContent, player: PlayerName, square: usize) -> Score {
+    // Flipping the rows of a square index only takes flipping the bits of
+    // the row
+    let square = if player == PlayerName::WHITE { square } else { square ^ 56 };
+    let middlegame = PIECE_SQUARE_TABLES[figure.index()][square];
+    let endgame = match figure {
+        SquareContent::PAWN => ENDGAME_PAWN_TABLE[square],
+        SquareContent::KING => ENDGAME_KING_TABLE[square],
+        _ => middlegame
+    };
+    return Score::new(middlegame, endgame)
+}
+
+pub fn evaluate(board: &Board) -> i32 {
+    return evaluate_with(board, &weights())
+}
+
+pub fn evaluate_with(board: &Board, weights: &Weights) -> i32 {
+    // How good the position is for the side to move, in centipawns. It's
+    // added up from white's point of view, and only turned around at the end
+    let mut score = Score::default();
+    let mut phase = 0;
+    for player in PLAYERS.iter() {
+        let mut own = Score::default();
+        for figure in FIGURES.iter() {
+            for square in squares(board.pieces[player.index()][figure.index()]) {
+                own += weights.material[figure.index()];
+                own += piece_square_score(*figure, *player, square);
+                phase += PHASE_WEIGHTS[figure.index()];
+            }
+        }
+        own += pawn_structure(board, *player, weights);
+        own += figure_placement(board, *player, weights);
+        own += king_safety(board, *player, weights);
+        own += figure_activity(board, *player, weights);
+
+        if *player == PlayerName::WHITE {
+            score += own;
+        } else {
+            score -= own;
+        }
+    }
+
+    let white = score.taper(phase);
+    return if board.state.side_to_move == PlayerName::WHITE { white } else { -white }
+}
+
+fn rows_ahead(player: PlayerName, y: usize) -> Bitboard {
+    // The rows a pawn of the player on row y still has to cross
+    if player == PlayerName::WHITE {
+        return rows_above(y)
+    }
+    return !rows_above(y + 1)
+}
+
+fn neighbouring_columns(x: usize) -> Bitboard {
+    let mut columns = 0;
+    if x > 0 {
+        columns |= column(x - 1);
+    }
+    if x + 1 < COLS {
+        columns |= column(x + 1);
+    }
+    return columns
+}
+
+fn pawn_structure(board: &Board, player: PlayerName, weights: &Weights) -> Score {
+    let pawn = SquareContent::PAWN.index();
+    let own = board.pieces[player.index()][pawn];
+    let theirs = board.pieces[player.opponent().index()][pawn];
+    let mut score = Score::default();
+
+    for x in 0..COLS {
+        let pawns = (own & column(x)).count_ones() as i32;
+        if pawns > 1 {
+            score += weights.doubled_pawn * (pawns - 1);
+        }
+    }
+
+    for square in squares(own) {
+        let (x, y) = square_coords(square);
+        let neighbours = own & neighbouring_columns(x);
+        let ahead = rows_ahead(player, y);
+
+        if neighbours == 0 {
+            score += weights.isolated_pawn;
+        } else if neighbours & !ahead == 0 {
+            // All the neighbours are further up the board, so none can defend
+            // the pawn as it moves up to them, which it can't do safely if
+            // a pawn of the opponent guards the square in front of it
+            let stop = if player == PlayerName::WHITE { square.checked_sub(COLS) } else { Some(square + COLS).filter(|stop| *stop < 64) };
+            if let Some(stop) = stop {
+                if PAWN_ATTACKS[player.index()][stop] & theirs != 0 {
+                    score += weights.backward_pawn;
+                }
+            }
+        }
+
+        if theirs & (column(x) | neighbouring_columns(x)) & ahead == 0 {
+            let rank = if player == PlayerName::WHITE { 7 - y } else { y };
+            score += weights.passed_pawn[rank];
+        }
+    }
+    return score
+}
+
+fn figure_placement(board: &Board, player: PlayerName, weights: &Weights) -> Score {
+    let pieces = &board.pieces[player.index()];
+    let mut score = Score::default();
+
+    let bishops = pieces[SquareContent::BISHOP.index()];
+    if bishops & LIGHT_SQUARES != 0 && bishops & !LIGHT_SQUARES != 0 {
+        score += weights.bishop_pair;
+    }
+
+    let own_pawns = pieces[SquareContent::PAWN.index()];
+    let pawns = own_pawns | board.pieces[player.opponent().index()][SquareContent::PAWN.index()];
+    for square in squares(pieces[SquareContent::ROOK.index()]) {
+        let file = column(square_coords(square).0);
+        if pawns & file == 0 {
+            score += weights.rook_on_open_file;
+        } else if own_pawns & file == 0 {
+            score += weights.rook_on_half_open_file;
+        }
+    }
+    return score
+}
+
+fn king_safety(board: &Board, player: PlayerName, weights: &Weights) -> Score {
+    // Pawns in front of the king keep the opponent's figures away from it.
+    // The attacks on the king are counted with the figures of the opponent
+    let king = board.pieces[player.index()][SquareContent::KING.index()];
+    let square = match squares(king).next() {
+        Some(square) => square,
+        None => return Score::default()
+    };
+    let (x, y) = square_coords(square);
+    let rows = if player == PlayerName::WHITE {
+        rows_above(y) & !rows_above(y.saturating_sub(2))
+    } else {
+        rows_above(y + 3) & !rows_above(y + 1)
+    };
+    let shield = board.pieces[player.index()][SquareContent::PAWN.index()] & (column(x) | neighbouring_columns(x)) & rows;
+    return weights.pawn_shield * shield.count_ones() as i32
+}
+
+fn figure_activity(board: &Board, player: PlayerName, weights: &Weights) -> Score {
+    // How many squares the figures can go to, leaving out the ones where a
+    // pawn would take them, and how many of those are next to the king of
+    // the opponent
+    let opponent = player.opponent();
+    let occupied = board.occupied[0] | board.occupied[1];
+    let mut guarded_by_pawns = 0;
+    for square in squares(board.pieces[opponent.index()][SquareContent::PAWN.index()]) {
+        guarded_by_pawns |= PAWN_ATTACKS[opponent.index()][square];
+    }
+    let area = !board.occupied[player.index()] & !guarded_by_pawns;
+    let king_zone = squares(board.pieces[opponent.index()][SquareContent::KING.index()]).next()
+        .map_or(0, |square| KING_ATTACKS[square] | 1 << square);
+
+    let mut score = Score::default();
+    for figure in [SquareContent::KNIGHT, SquareContent::BISHOP, SquareContent::ROOK, SquareContent::QUEEN].iter() {
+        for square in squares(board.pieces[player.index()][figure.index()]) {
+            let attacks = match figure {
+                SquareContent::KNIGHT => KNIGHT_ATTACKS[square],
+                SquareContent::BISHOP => bishop_attacks(square, occupied),
+                SquareContent::ROOK => rook_attacks(square, occupied),
+                _ => queen_attacks(square, occupied)
+            };
+            score += weights.mobility[figure.index()] * (attacks & area).count_ones() as i32;
+            score += weights.king_attack[figure.index()] * (attacks & king_zone).count_ones() as i32;
+        }
+    }
+    return score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DEFAULT_WEIGHTS, Score, Weights, evaluate, evaluate_with, figure_activity, figure_placement, king_safety, pawn_structure};
+    use crate::board::{Board, PlayerName};
+
+    #[test]
+    fn symmetric_positions() {
+        // The same position with the colours swapped is just as good for the
+        // other side
+        let fens = [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1"),
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", "r3k2r/pppbbppp/2n2q1P/1P2p3/3pn3/BN2PNP1/P1PPQPB1/R3K2R b KQkq - 0 1"),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", "8/4p1p1/8/1r3P1K/kp5R/3P4/2P5/8 b - - 0 1"),
+        ];
+        for (white, black) in fens.iter() {
+            assert_eq!(evaluate(&Board::from_fen(white).unwrap()), evaluate(&Board::from_fen(black).unwrap()), "{}", white);
+        }
+        assert_eq!(evaluate(&Board::from_fen(fens[0].0).unwrap()), 0);
+    }
+
+    #[test]
+    fn side_to_move() {
+        let white = Board::from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+        let black = Board::from_fen("4k3/8/8/8/8/8/8/3QK3 b - - 0 1").unwrap();
+        assert!(evaluate(&white) > 800);
+        assert_eq!(evaluate(&white), -evaluate(&black));
+    }
+
+    #[test]
+    fn figures_prefer_good_squares() {
+        // A knight in the centre against one on the rim, and a pushed pawn
+        let better = ["4k3/8/8/8/3N4/8/8/4K3 w - - 0 1", "4k3/8/8/4P3/8/8/8/4K3 w - - 0 1"];
+        let worse = ["4k3/8/8/8/8/8/8/N3K3 w - - 0 1", "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"];
+        for (better, worse) in better.iter().zip(worse.iter()) {
+            assert!(evaluate(&Board::from_fen(better).unwrap()) > evaluate(&Board::from_fen(worse).unwrap()), "{}", better);
+        }
+    }
+
+    #[test]
+    fn game_phase() {
+        // The king stays home while the opponent still has an army, and walks
+        // to the centre once it doesn't
+        let home = evaluate(&Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w kq - 0 1").unwrap());
+        let centre = evaluate(&Board::from_fen("rnbqkbnr/pppppppp/8/8/4K3/8/PPPPPPPP/RNBQ1BNR w kq - 0 1").unwrap());
+        assert!(home > centre);
+        let home = evaluate(&Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap());
+        let centre = evaluate(&Board::from_fen("4k3/8/8/8/4K3/8/8/8 w - - 0 1").unwrap());
+        assert!(home < centre);
+
+        assert_eq!(Score::new(100, 200).taper(24), 100);
+        assert_eq!(Score::new(100, 200).taper(12), 150);
+        assert_eq!(Score::new(100, 200).taper(0), 200);
+    }
+
+    fn count(fen: &str, term: &str) -> i32 {
+        // How many times white gets the term, with every other weight left
+        // out
+        let board = Board::from_fen(fen).unwrap();
+        let mut weights = Weights::default();
+        match term {
+            "passed_pawn" => weights.passed_pawn = [Score::new(1, 1); 8],
+            "mobility" | "king_attack" => *weights.figure_term(term).unwrap() = [Score::new(1, 1); 6],
+            term => *weights.term(term).unwrap() = Score::new(1, 1)
+        }
+        let player = PlayerName::WHITE;
+        let mut total = pawn_structure(&board, player, &weights);
+        total += figure_placement(&board, player, &weights);
+        total += king_safety(&board, player, &weights);
+        total += figure_activity(&board, player, &weights);
+        return total.middlegame
+    }
+
+    #[test]
+    fn pawn_structure_terms() {
+        let fen = "4k3/8/8/8/8/4P3/4P3/4K3 w - - 0 1";
+        assert_eq!(count(fen, "doubled_pawn"), 1);
+        assert_eq!(count(fen, "isolated_pawn"), 2);
+        assert_eq!(count(fen, "backward_pawn"), 0);
+        assert_eq!(count(fen, "passed_pawn"), 2);
+
+        // The pawn on c3 can't get to c4 without being taken, and the b5
+        // pawn stops it from passing; the one on d4 has a free run
+        let fen = "4k3/8/8/1p6/3P4/2P5/8/4K3 w - - 0 1";
+        assert_eq!(count(fen, "doubled_pawn"), 0);
+        assert_eq!(count(fen, "isolated_pawn"), 0);
+        assert_eq!(count(fen, "backward_pawn"), 1);
+        assert_eq!(count(fen, "passed_pawn"), 1);
+
+        // Ranks count from the pawn's own side
+        let board = Board::from_fen("4k3/8/8/3P4/8/8/8/4K3 w - - 0 1").unwrap();
+        let score = pawn_structure(&board, PlayerName::WHITE, &DEFAULT_WEIGHTS);
+        let mut expected = DEFAULT_WEIGHTS.passed_pawn[4];
+        expected += DEFAULT_WEIGHTS.isolated_pawn;
+        assert_eq!(score, expected);
+    }
+
+    #[test]
+    fn figure_terms() {
+        // The bishops on c1 and f1 stand on squares of different colours
+        let fen = "4k3/8/8/8/8/8/P7/R1B1KB1R w - - 0 1";
+        assert_eq!(count(fen, "bishop_pair"), 1);
+        assert_eq!(count("4k3/8/8/8/8/8/P7/RB2KB1R w - - 0 1", "bishop_pair"), 0);
+        assert_eq!(count(fen, "rook_on_open_file"), 1);
+        assert_eq!(count(fen, "rook_on_half_open_file"), 0);
+        let fen = "4k3/7p/8/8/8/8/P7/R1B1KB1R w - - 0 1";
+        assert_eq!(count(fen, "rook_on_open_file"), 0);
+        assert_eq!(count(fen, "rook_on_half_open_file"), 1);
+    }
+
+    #[test]
+    fn changed_weights() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/P7/R1B1KB1R w - - 0 1").unwrap();
+        let mut weights = DEFAULT_WEIGHTS;
+        weights.bishop_pair = Score::new(1000, 1000);
+        assert!(evaluate_with(&board, &weights) > evaluate_with(&board, &DEFAULT_WEIGHTS) + 900);
+        assert_eq!(evaluate_with(&board, &DEFAULT_WEIGHTS), evaluate(&board));
+    }
+
+    #[test]
+    fn king_safety_terms() {
+        assert_eq!(count("6k1/8/8/8/8/8/5PPP/6K1 w - - 0 1", "pawn_shield"), 3);
+        assert_eq!(count("6k1/8/8/8/8/7P/5PP1/6K1 w - - 0 1", "pawn_shield"), 3);
+        assert_eq!(count("6k1/8/8/8/6P1/8/5P1P/6K1 w - - 0 1", "pawn_shield"), 2);
+        assert_eq!(count("6k1/8/8/8/8/8/PPP5/6K1 w - - 0 1", "pawn_shield"), 0);
+
+        // The queen sees g7 and h8 along the long diagonal, and the rook
+        // would see g8 if the knight didn't block it
+        assert_eq!(count("6k1/8/8/8/8/8/8/Q3K3 w - - 0 1", "king_attack"), 2);
+        assert_eq!(count("1R2n1k1/8/8/8/8/8/8/4K3 w - - 0 1", "king_attack"), 0);
+
+        // Castled behind its pawns, the king is safer than in the centre
+        let castled = evaluate(&Board::from_fen("r1bq1rk1/ppp2ppp/2n2n2/3pp3/8/2NP1NP1/PPPQPPBP/R4RK1 b - - 0 1").unwrap());
+        let central = evaluate(&Board::from_fen("r1bq1rk1/ppp2ppp/2n2n2/3pp3/8/2NP1NP1/PPPQPPBP/R3K2R b - - 0 1").unwrap());
+        assert!(castled < central);
+    }
+
+    #[test]
+    fn mobility() {
+        // The knight on a1 can go to b3 and c2, unless a pawn guards b3
+        assert_eq!(count("6k1/8/8/8/8/8/8/N3K3 w - - 0 1", "mobility"), 2);
+        assert_eq!(count("6k1/8/8/8/p7/8/8/N3K3 w - - 0 1", "mobility"), 1);
+        // A rook sees up to the first figure on each side, and takes the
+        // opponent's
+        assert_eq!(count("6k1/8/8/8/8/8/8/R2nK3 w - - 0 1", "mobility"), 7 + 3);
+    }
+}
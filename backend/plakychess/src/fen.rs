@@ -0,0 +1,204 @@
+use crate::bitboard::{COLS, ROWS, parse_square, square_index, square_name};
+use crate::board::{Board, CastlingRights, GameState, PlayerName, SquareContent};
+
+impl Board {
+    pub fn from_fen(fen: &str) -> Result<Board, String> {
+        // Forsyth-Edwards Notation describes the squares row by row from the
+        // top of the board, followed by the side to move, castling rights,
+        // en passant square, halfmove clock and fullmove number. The two
+        // clocks are often left out, and default to the start of a game.
+        // Positions the move generator can't handle, without exactly one
+        // king per side or with pawns on the first or last row, are rejected
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() < 4 || fields.len() > 6 {
+            return Err(format!("Invalid FEN {:?}, expected 4 to 6 fields", fen))
+        }
+
+        let mut board = Board::empty();
+        let rows: Vec<&str> = fields[0].split('/').collect();
+        if rows.len() != ROWS {
+            return Err(format!("Invalid FEN {:?}, expected {} rows", fen, ROWS))
+        }
+        for (y, row) in rows.iter().enumerate() {
+            let mut x = 0;
+            for square in row.chars() {
+                if let Some(empty) = square.to_digit(10) {
+                    x += empty as usize;
+                    continue;
+                }
+                let content = SquareContent::from_letter(square)
+                    .ok_or_else(|| format!("Invalid figure {:?} in FEN {:?}", square, fen))?;
+                let player = if square.is_ascii_uppercase() { PlayerName::WHITE } else { PlayerName::BLACK };
+                if x >= COLS {
+                    return Err(format!("Invalid FEN {:?}, row {:?} isn't {} squares wide", fen, row, COLS))
+                }
+                board.put(square_index((x, y)), content, player);
+                x += 1;
+            }
+            if x != COLS {
+                return Err(format!("Invalid FEN {:?}, row {:?} isn't {} squares wide", fen, row, COLS))
+            }
+        }
+        board.validate().map_err(|error| format!("Invalid FEN {:?}, {}", fen, error))?;
+
+        let side_to_move = match fields[1] {
+            "w" => PlayerName::WHITE,
+            "b" => PlayerName::BLACK,
+            x => return Err(format!("Invalid side to move {:?} in FEN {:?}", x, fen))
+        };
+        let castling_rights = CastlingRights::parse(fields[2])
+            .ok_or_else(|| format!("Invalid castling rights {:?} in FEN {:?}", fields[2], fen))?;
+        // The square a pawn skipped with its double push, which is behind
+        // the pawns of the player who just moved
+        let en_passant_row = if side_to_move == PlayerName::WHITE { 2 } else { ROWS - 3 };
+        let en_passant = match fields[3] {
+            "-" => None,
+            x => match parse_square(x) {
+                Some(square) if square.1 == en_passant_row => Some(square),
+                _ => return Err(format!("Invalid en passant square {:?} in FEN {:?}", x, fen))
+            }
+        };
+        let clock = |index: usize, default: u32| match fields.get(index) {
+            Some(x) => x.parse().map_err(|_| format!("Invalid move counter {:?} in FEN {:?}", x, fen)),
+            None => Ok(default)
+        };
+
+        board.set_state(GameState {
+            side_to_move,
+            castling_rights,
+            en_passant,
+            halfmove_clock: clock(4, 0)?,
+            fullmove_number: clock(5, 1)?
+        });
+        return Ok(board)
+    }
+
+    pub fn to_fen(&self) -> String {
+        // The inverse of `Board::from_fen`, always with all six fields
+        let mut rows = Vec::new();
+        for y in 0..ROWS {
+            let mut row = String::new();
+            let mut empty = 0;
+            for x in 0..COLS {
+                let (content, player) = self.figure_at((x, y));
+                if content == SquareContent::EMPTY {
+                    empty += 1;
+                    continue;
+                }
+                if empty > 0 {
+                    row.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                let letter = content.letter();
+                row.push(if player == PlayerName::WHITE { letter } else { letter.to_ascii_lowercase() });
+            }
+            if empty > 0 {
+                row.push_str(&empty.to_string());
+            }
+            rows.push(row);
+        }
+
+        let state = &self.state;
+        return format!(
+            "{} {} {} {} {} {}",
+            rows.join("/"),
+            if state.side_to_move == PlayerName::WHITE { "w" } else { "b" },
+            state.castling_rights.description(),
+            state.en_passant.map_or("-".to_string(), square_name),
+            state.halfmove_clock,
+            state.fullmove_number
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{Board, PlayerName, SquareContent};
+
+    const START_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    #[test]
+    fn start_position() {
+        let board = Board::from_fen(START_POSITION).unwrap();
+        assert_eq!(board.figure_at((0, 0)), (SquareContent::ROOK, PlayerName::BLACK));
+        assert_eq!(board.figure_at((4, 7)), (SquareContent::KING, PlayerName::WHITE));
+        assert_eq!(board.figure_at((3, 6)), (SquareContent::PAWN, PlayerName::WHITE));
+        assert_eq!(board.figure_at((3, 3)), (SquareContent::EMPTY, PlayerName::NEITHER));
+        assert!(board.state == crate::board::GameState::new());
+        assert_eq!(board.to_fen(), START_POSITION);
+    }
+
+    #[test]
+    fn round_trip() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 12 40"
+        ].iter() {
+            assert_eq!(Board::from_fen(fen).unwrap().to_fen(), *fen);
+        }
+    }
+
+    #[test]
+    fn missing_clocks() {
+        let board = Board::from_fen("8/8/8/8/8/8/8/K6k b - e3").unwrap();
+        assert_eq!(board.state.en_passant, Some((4, 5)));
+        assert_eq!(board.to_fen(), "8/8/8/8/8/8/8/K6k b - e3 0 1");
+    }
+
+    #[test]
+    fn same_key_as_played_position() {
+        let mut played = Board::from_fen(START_POSITION).unwrap();
+        let double_push = played.legal_moves().into_iter().find(|mv| mv.from == (4, 6) && mv.to == (4, 4)).unwrap();
+        played.make_move(&double_push);
+        let loaded = Board::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        assert_eq!(played.to_fen(), loaded.to_fen());
+        assert_eq!(played.position_key(), loaded.position_key());
+    }
+
+    #[test]
+    fn en_passant_only_counts_when_possible() {
+        // Without a pawn beside the one which just moved, nothing can take
+        // it en passant, and the position is the same as without the square
+        let key = |fen: &str| Board::from_fen(fen).unwrap().position_key();
+        assert_eq!(key("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1"), key("4k3/8/8/8/4P3/8/8/4K3 b - - 0 1"));
+        assert_eq!(key("4k3/8/8/8/2p1P3/8/8/4K3 b - e3 0 1"), key("4k3/8/8/8/2p1P3/8/8/4K3 b - - 0 1"));
+        assert_ne!(key("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1"), key("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1"));
+        assert_ne!(key("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1"), key("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1"));
+
+        // The key is kept up to date the same way while moves are played
+        // and taken back
+        let mut board = Board::from_fen("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1").unwrap();
+        let before = board.position_key();
+        let double_push = board.parse_uci_move("e2e4").unwrap();
+        board.make_move(&double_push);
+        assert_eq!(board.position_key(), key("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1"));
+        board.unmake_move();
+        assert_eq!(board.position_key(), before);
+    }
+
+    #[test]
+    fn invalid_fens() {
+        for fen in [
+            // Too few squares, too many, too many figures, an unknown figure
+            "rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnrr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1",
+            // Missing fields, and invalid ones
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQxq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e9 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1",
+            // Pawns on the last rows, and the wrong number of kings
+            "P3k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/p3K3 w - - 0 1",
+            "8/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/3KK3 w - - 0 1",
+        ].iter() {
+            assert!(Board::from_fen(fen).is_err(), "{}", fen);
+        }
+    }
+}
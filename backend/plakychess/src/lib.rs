@@ -1,693 +1,565 @@
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use std::collections::HashMap;
+use std::time::Duration;
+
+mod bitboard;
+mod board;
+mod engine;
+mod eval;
+mod fen;
+mod magic;
+mod movegen;
+mod perft;
+mod pgn;
+mod random;
+mod san;
+mod search;
+mod transposition;
+mod uci;
+mod zobrist;
+
+pub use board::{Board, CastlingRights, DrawReason, GameState, GameStatus, PlayerName, SquareContent};
+pub use engine::{RandomStrategy, Strategy};
+pub use eval::{Score, Weights};
+pub use movegen::Move;
+pub use pgn::{Game, GameMove};
+pub use search::{AlphaBetaStrategy, Search, SearchLimits, SearchResult};
+
+fn build_board(board_description: &[HashMap<String, String>], state: Option<&PyDict>, position_history: Vec<u64>) -> PyResult<Board> {
+    // Constructs a Board from the description the frontend sends, together
+    // with the parts of the game state which can't be read off the squares
+    let mut board = Board::new(board_description).map_err(PyValueError::new_err)?;
+    board.set_state(parse_state(state)?);
+    board.position_history = position_history;
+    Ok(board)
+}
 
-#[pyfunction]
-fn get_next_move(board_description: Vec<HashMap<String, String>>) -> PyResult<([(usize, usize); 2], Vec<[Vec<(usize, usize)>; COLS]>)> {
-    // Constructs a Board, finds the next move, and generates available moves
-    // for the player
-    let board = Board::new(&board_description);
+fn parse_state(state: Option<&PyDict>) -> PyResult<GameState> {
+    // Reads the state in the form `GameState::into_py` writes it. Every key
+    // is optional, and a missing one means white to move, with no castling
+    // rights nor en passant square, at the start of the clocks
+    let mut parsed = GameState { castling_rights: CastlingRights::none(), ..GameState::new() };
+    let state = match state {
+        Some(state) => state,
+        None => return Ok(parsed)
+    };
+    for (key, value) in state.iter() {
+        match key.extract::<&str>()? {
+            "side_to_move" => parsed.side_to_move = parse_player(value.extract()?)?,
+            "castling_rights" => {
+                if let Some(castling_rights) = value.extract::<Option<&str>>()? {
+                    parsed.castling_rights = CastlingRights::parse(castling_rights)
+                        .ok_or_else(|| PyValueError::new_err(format!("Invalid castling rights {:?}", castling_rights)))?;
+                }
+            },
+            // JSON has no tuples, so a list is as good as one here
+            "en_passant" => parsed.en_passant = match value.extract::<Option<Vec<usize>>>()? {
+                Some(square) => match square[..] {
+                    [x, y] => Some((x, y)),
+                    _ => return Err(PyValueError::new_err(format!("Invalid en passant square {:?}", square)))
+                },
+                None => None
+            },
+            "halfmove_clock" => parsed.halfmove_clock = value.extract()?,
+            "fullmove_number" => parsed.fullmove_number = value.extract()?,
+            key => return Err(PyValueError::new_err(format!("Invalid game state {:?}", key)))
+        }
+    }
 
-    // Get bot move
-    // Currenly hardcoded, next step will be random
-    let bot_move = [(3, 4), (4, 3)];
+    // Only the square a pawn just skipped can be taken en passant, which is
+    // behind the pawns of the side which just moved
+    if let Some((x, y)) = parsed.en_passant {
+        let en_passant_row = if parsed.side_to_move == PlayerName::WHITE { 2 } else { bitboard::ROWS - 3 };
+        if x >= bitboard::COLS || y != en_passant_row {
+            return Err(PyValueError::new_err(format!("Invalid en passant square {:?}", (x, y))))
+        }
+    }
+    Ok(parsed)
+}
 
-    // Get all available moves for player
-    let available_player_moves = Vec::from(board.get_available_moves(PlayerName::WHITE));
+fn parse_figure(figure: &str) -> PyResult<SquareContent> {
+    match figure {
+        "PAWN" => Ok(SquareContent::PAWN),
+        "KNIGHT" => Ok(SquareContent::KNIGHT),
+        "BISHOP" => Ok(SquareContent::BISHOP),
+        "ROOK" => Ok(SquareContent::ROOK),
+        "QUEEN" => Ok(SquareContent::QUEEN),
+        "KING" => Ok(SquareContent::KING),
+        x => Err(PyValueError::new_err(format!("Invalid figure type {:?}", x)))
+    }
+}
 
-    Ok((bot_move, available_player_moves))
+fn parse_player(player: &str) -> PyResult<PlayerName> {
+    match player {
+        "WHITE" => Ok(PlayerName::WHITE),
+        "BLACK" => Ok(PlayerName::BLACK),
+        x => Err(PyValueError::new_err(format!("Invalid player type {:?}", x)))
+    }
 }
 
-/// A Python module implemented in Rust
-#[pymodule]
-fn plakychess(_py: Python, m: &PyModule) -> PyResult<()> {
-    m.add_function(wrap_pyfunction!(get_next_move, m)?)?;
-    Ok(())
+fn search_limits(depth: Option<u32>, time_ms: Option<u64>, nodes: Option<u64>, default_depth: u32) -> SearchLimits {
+    // Without a depth, the search goes as deep as the time and nodes allow,
+    // or to the default depth if it isn't given either of those
+    let depth = match depth {
+        Some(depth) => depth,
+        None if time_ms.is_some() || nodes.is_some() => search::MAX_DEPTH,
+        None => default_depth
+    };
+    SearchLimits { depth, time: time_ms.map(Duration::from_millis), nodes }
 }
 
-const ROWS: usize = 8;
-const COLS: usize = 8;
-
-#[derive(PartialEq, Copy, Clone)]
-pub enum PlayerName {
-    WHITE,
-    BLACK,
-    NEITHER
-}
-
-#[derive(PartialEq, Copy, Clone)]
-enum SquareContent {
-    PAWN,
-    KNIGHT,
-    BISHOP,
-    ROOK,
-    QUEEN,
-    KING,
-    EMPTY
-}
-
-pub struct Board {
-    board_state: [[Square; COLS]; ROWS]
-}
-
-impl Board {
-    pub fn new(board_description: &Vec<HashMap<String, String>>) -> Self {
-        let mut board_state = [[Square { x: 0, y: 0, content: SquareContent::EMPTY, belongs_to: PlayerName::NEITHER }; COLS]; ROWS];
-        for square in board_description.iter() {
-            let x = square.get("x").unwrap().parse::<usize>().unwrap();
-            let y = square.get("y").unwrap().parse::<usize>().unwrap();
-            board_state[y][x].x = x;
-            board_state[y][x].y = y;
-            match square.get("figure").unwrap().as_str() {
-                "PAWN" => board_state[y][x].content = SquareContent::PAWN,
-                "KNIGHT" => board_state[y][x].content = SquareContent::KNIGHT,
-                "BISHOP" => board_state[y][x].content = SquareContent::BISHOP,
-                "ROOK" => board_state[y][x].content = SquareContent::ROOK,
-                "QUEEN" => board_state[y][x].content = SquareContent::QUEEN,
-                "KING" => board_state[y][x].content = SquareContent::KING,
-                "EMPTY" => board_state[y][x].content = SquareContent::EMPTY,
-                x => panic!("Invalid figure type {:?}", x)
-            }
-            match square.get("belongs_to").unwrap().as_str() {
-                "WHITE" => board_state[y][x].belongs_to = PlayerName::WHITE,
-                "BLACK" => board_state[y][x].belongs_to = PlayerName::BLACK,
-                "NEITHER" => board_state[y][x].belongs_to = PlayerName::NEITHER,
-                x => panic!("Invalid player type {:?}", x)
-            }
-        }
-        return Board { board_state }
+fn strategy_by_name(name: &str, limits: SearchLimits) -> PyResult<Box<dyn Strategy + Send>> {
+    match name {
+        "random" => Ok(Box::new(RandomStrategy::from_time())),
+        "alphabeta" => Ok(Box::new(AlphaBetaStrategy { limits })),
+        x => Err(PyValueError::new_err(format!("Invalid strategy {:?}", x)))
     }
+}
 
-    pub fn get_moves(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
-        return self.board_state[y][x].get_moves(&self.board_state);
+// Python passes every argument separately, so they can't be grouped
+#[allow(clippy::too_many_arguments)]
+#[pyfunction(state = "None", position_history = "Vec::new()", strategy = "\"alphabeta\"", depth = "None", time_ms = "None", nodes = "None")]
+fn get_next_move(py: Python, board_description: Vec<HashMap<String, String>>, state: Option<&PyDict>, position_history: Vec<u64>, strategy: &str, depth: Option<u32>, time_ms: Option<u64>, nodes: Option<u64>) -> PyResult<PyObject> {
+    // Constructs a Board, lets the bot play its move on it, and describes
+    // the position the player ends up in: its board and state, and the
+    // moves the player can make in it
+    let mut board = build_board(&board_description, state, position_history)?;
+    let mut strategy = strategy_by_name(strategy, search_limits(depth, time_ms, nodes, 3))?;
+    let response = PyDict::new(py);
+
+    // There's no move to make once the game is over
+    // Other Python threads, like the server's other requests, can go on
+    // while the bot thinks
+    let bot_move = if board.status() == GameStatus::ONGOING { py.allow_threads(|| strategy.choose_move(&mut board)) } else { None };
+    match bot_move {
+        Some(bot_move) => {
+            response.set_item("move", move_with_san_into_py(py, &bot_move, &board))?;
+            board.make_move(&bot_move);
+        },
+        None => response.set_item("move", py.None())?
     }
+    response.set_item("board", board.description())?;
+    response.set_item("state", board.state.into_py(py))?;
+
+    // Only the positions since the last capture or pawn move can still be
+    // repeated, so those are all the caller has to keep for the next call
+    let mut position_history = board.recent_positions().to_vec();
+    position_history.push(board.position_key());
+    response.set_item("position_history", position_history)?;
+
+    let status = board.status();
+    response.set_item("status", status.into_py(py))?;
+    let available_moves: Vec<PyObject> = if status == GameStatus::ONGOING {
+        board.legal_moves().iter().map(|mv| move_with_san_into_py(py, mv, &board)).collect()
+    } else {
+        Vec::new()
+    };
+    response.set_item("available_moves", available_moves)?;
+
+    Ok(response.into())
+}
 
-    pub fn get_available_moves(&self, player: PlayerName) -> [[Vec<(usize, usize)>; COLS]; ROWS] {
-        // Loop through board to find the player's available pieces, and get
-        // possible moves for every single one
-        let mut available_moves: [[Vec<(usize, usize)>; COLS]; ROWS] = Default::default();
+#[pyfunction(state = "None", position_history = "Vec::new()")]
+fn get_available_moves(py: Python, board_description: Vec<HashMap<String, String>>, state: Option<&PyDict>, position_history: Vec<u64>) -> PyResult<Vec<PyObject>> {
+    // Generates all legal moves of the side to move, with their SAN
+    let board = build_board(&board_description, state, position_history)?;
+    Ok(board.legal_moves().iter().map(|mv| move_with_san_into_py(py, mv, &board)).collect())
+}
 
-        for column in self.board_state.iter() {
-            for square in column.iter() {
-                if square.belongs_to != player {
-                    continue;
-                }
-                let moves = square.get_moves(&self.board_state);
-                if moves.len() > 0 {
-                    available_moves[square.y][square.x].extend(moves);
-                }
-            }
-        }
-        return available_moves
+#[pyfunction(state = "None", position_history = "Vec::new()")]
+fn parse_san(py: Python, board_description: Vec<HashMap<String, String>>, san: &str, state: Option<&PyDict>, position_history: Vec<u64>) -> PyResult<PyObject> {
+    // Finds the legal move of the side to move written in SAN, like "Nbd7"
+    let board = build_board(&board_description, state, position_history)?;
+    match board.parse_san(san) {
+        Some(mv) => Ok(move_with_san_into_py(py, &mv, &board)),
+        None => Err(PyValueError::new_err(format!("Illegal or ambiguous move {:?} in position {}", san, board.to_fen())))
     }
 }
 
-#[derive(Copy, Clone)]
-struct Square {
-    x: usize,
-    y: usize,
-    content: SquareContent,
-    belongs_to: PlayerName
-}
-
-impl Square {
-    fn get_moves(&self, board_state: &[[Square; COLS]; ROWS]) -> Vec<(usize, usize)> {
-        match self.content {
-            SquareContent::PAWN => { return self.pawn_moves(&board_state) },
-            SquareContent::KNIGHT => { return self.knight_moves(&board_state) },
-            SquareContent::BISHOP => { return self.bishop_moves(&board_state) },
-            SquareContent::ROOK => { return self.rook_moves(&board_state) },
-            SquareContent::QUEEN => { return self.queen_moves(&board_state) },
-            SquareContent::KING => { return self.king_moves(&board_state) },
-            SquareContent::EMPTY =>  { return Vec::new() }
-        }
+#[pyfunction(state = "None", position_history = "Vec::new()")]
+fn parse_uci_move(py: Python, board_description: Vec<HashMap<String, String>>, uci: &str, state: Option<&PyDict>, position_history: Vec<u64>) -> PyResult<PyObject> {
+    // Finds the legal move of the side to move written in UCI, like "e7e8q"
+    let board = build_board(&board_description, state, position_history)?;
+    match board.parse_uci_move(uci) {
+        Some(mv) => Ok(move_with_san_into_py(py, &mv, &board)),
+        None => Err(PyValueError::new_err(format!("Illegal move {:?} in position {}", uci, board.to_fen())))
     }
+}
 
-    fn pawn_moves(&self, board_state: &[[Square; COLS]; ROWS]) -> Vec<(usize, usize)> {
-        // The pawn has five different moves:
-        // - move vertically
-        // - move diagonally to the left
-        // - move diagonally to the right
-        // - additional square on first move
-        // - en-passant move
-        let mut moves = Vec::new();
-
-        // Check whether we move up or down on the board, and then define all
-        // four possible moves
-        if self.belongs_to == PlayerName::WHITE {
-
-            // We add this move only if there are no figures in front of the
-            // pawn, and we're not on the edge of the board
-            if self.y > 0 && board_state[self.y - 1][self.x].content == SquareContent::EMPTY {
-              moves.push((self.x, self.y - 1));
-
-              // If it's the first move of a pawn, a pawn gets to move an
-              // additional square; I threw this check inside the above check
-              // because if there's a figure directly in front of the pawn,
-              // this move is not valid
-              if self.y == ROWS - 2 && board_state[self.y - 2][self.x].content == SquareContent::EMPTY {
-                moves.push((self.x, self.y - 2));
-              }
-            }
+#[pyfunction(state = "None", position_history = "Vec::new()")]
+fn make_move(py: Python, board_description: Vec<HashMap<String, String>>, mv: Move, state: Option<&PyDict>, position_history: Vec<u64>) -> PyResult<PyObject> {
+    // Plays the legal move of the side to move, given like the moves the
+    // other functions return or in UCI, and describes the position it leads
+    // to the same way `get_next_move` does
+    let mut board = build_board(&board_description, state, position_history)?;
+    let legal = board.legal_moves().into_iter()
+        .find(|legal| legal.from == mv.from && legal.to == mv.to && legal.promotion == mv.promotion)
+        .ok_or_else(|| PyValueError::new_err(format!("Illegal move from {:?} to {:?} in position {}", mv.from, mv.to, board.to_fen())))?;
+    let response = PyDict::new(py);
+    response.set_item("move", move_with_san_into_py(py, &legal, &board))?;
+    board.make_move(&legal);
+    response.set_item("board", board.description())?;
+    response.set_item("state", board.state.into_py(py))?;
+    let mut position_history = board.recent_positions().to_vec();
+    position_history.push(board.position_key());
+    response.set_item("position_history", position_history)?;
+    response.set_item("status", board.status().into_py(py))?;
+    Ok(response.into())
+}
 
-            // Check if we can move diagonally
-            if self.y > 0 && self.x > 0 && board_state[self.y - 1][self.x - 1].content != SquareContent::EMPTY {
-              if board_state[self.y - 1][self.x - 1].belongs_to != self.belongs_to {
-                moves.push((self.x - 1, self.y - 1));
-              }
-            }
+#[pyfunction]
+fn square_name(square: (usize, usize)) -> PyResult<String> {
+    if square.0 >= bitboard::COLS || square.1 >= bitboard::ROWS {
+        return Err(PyValueError::new_err(format!("Invalid square {:?}", square)))
+    }
+    Ok(bitboard::square_name(square))
+}
 
-            if self.y > 0 && self.x < COLS - 1 && board_state[self.y - 1][self.x + 1].content != SquareContent::EMPTY {
-              if board_state[self.y - 1][self.x + 1].belongs_to != self.belongs_to {
-                moves.push((self.x + 1, self.y - 1));
-              }
-            }
+#[pyfunction]
+fn parse_square(name: &str) -> PyResult<(usize, usize)> {
+    bitboard::parse_square(name).ok_or_else(|| PyValueError::new_err(format!("Invalid square {:?}", name)))
+}
 
-        } else {
+#[pyfunction(state = "None", position_history = "Vec::new()")]
+fn get_game_status(board_description: Vec<HashMap<String, String>>, state: Option<&PyDict>, position_history: Vec<u64>) -> PyResult<GameStatus> {
+    // Tells whether the game is still going on, and if not, how it ended
+    let board = build_board(&board_description, state, position_history)?;
+    Ok(board.status())
+}
 
-            // We add this move only if there are no figures in front of the
-            // pawn, and we're not on the edge of the board
-            if self.y < ROWS - 1 && board_state[self.y + 1][self.x].content == SquareContent::EMPTY {
-              moves.push((self.x, self.y + 1));
+#[pyfunction(depth = "None", time_ms = "None", nodes = "None", state = "None", position_history = "Vec::new()")]
+#[pyo3(name = "search")]
+fn search_position(py: Python, board_description: Vec<HashMap<String, String>>, depth: Option<u32>, time_ms: Option<u64>, nodes: Option<u64>, state: Option<&PyDict>, position_history: Vec<u64>) -> PyResult<PyObject> {
+    // Searches the position within the given depth, time in milliseconds and
+    // number of nodes, without playing any move
+    let mut board = build_board(&board_description, state, position_history)?;
+    let limits = search_limits(depth, time_ms, nodes, 4);
+    let result = py.allow_threads(|| Search::new(limits, &mut transposition::shared_table()).run(&mut board));
+    Ok(search_result_into_py(py, &result, &mut board))
+}
 
-              // If it's the first move of a pawn, a pawn gets to move an
-              // additional square; I threw this check inside the above check
-              // because if there's a figure directly in front of the pawn,
-              // this move is not valid
-              if self.y == 1 && board_state[self.y + 1][self.x].content == SquareContent::EMPTY {
-                moves.push((self.x, self.y + 2));
-              }
-            }
+#[pyfunction(state = "None", position_history = "Vec::new()")]
+#[pyo3(name = "evaluate")]
+fn evaluate_position(board_description: Vec<HashMap<String, String>>, state: Option<&PyDict>, position_history: Vec<u64>) -> PyResult<i32> {
+    // How good the position is for the side to move, in centipawns, without
+    // searching any moves
+    let board = build_board(&board_description, state, position_history)?;
+    Ok(eval::evaluate(&board))
+}
 
-            // Check if we can move diagonally
-            if self.y < ROWS - 1 && self.x > 0 && board_state[self.y + 1][self.x - 1].content != SquareContent::EMPTY {
-              if board_state[self.y + 1][self.x - 1].belongs_to != self.belongs_to {
-                moves.push((self.x - 1, self.y + 1));
-              }
-            }
+#[pyfunction]
+fn get_eval_weights(py: Python) -> PyResult<PyObject> {
+    // Every weight as a pair of middlegame and endgame scores; the material,
+    // mobility and king attacks by figure, and the passed pawns by rank as
+    // seen by their own side
+    Ok(weights_into_py(py, &eval::weights()))
+}
 
-            if self.y < ROWS - 1 && self.x < COLS - 1 && board_state[self.y + 1][self.x + 1].content != SquareContent::EMPTY {
-              if board_state[self.y + 1][self.x + 1].belongs_to != self.belongs_to {
-                moves.push((self.x + 1, self.y + 1));
-              }
+#[pyfunction]
+fn set_eval_weights(weights: HashMap<String, &PyAny>) -> PyResult<()> {
+    // Changes the given weights, in the same form `get_eval_weights` returns
+    // them, leaving the rest as they are
+    let mut new_weights = eval::weights();
+    for (name, value) in weights.iter() {
+        match name.as_str() {
+            "material" | "mobility" | "king_attack" => {
+                let scores = new_weights.figure_term(name).unwrap();
+                for (figure, score) in value.extract::<HashMap<String, &PyAny>>()? {
+                    let figure = board::FIGURES.iter().find(|content| content.name() == figure)
+                        .ok_or_else(|| PyValueError::new_err(format!("Invalid figure {:?}", figure)))?;
+                    scores[figure.index()] = extract_score(score)?;
+                }
+            },
+            "passed_pawn" => {
+                let scores: Vec<&PyAny> = value.extract()?;
+                if scores.len() != new_weights.passed_pawn.len() {
+                    return Err(PyValueError::new_err(format!("Expected {} passed pawn scores, got {}", new_weights.passed_pawn.len(), scores.len())))
+                }
+                for (rank, score) in scores.into_iter().enumerate() {
+                    new_weights.passed_pawn[rank] = extract_score(score)?;
+                }
+            },
+            term => match new_weights.term(term) {
+                Some(weight) => *weight = extract_score(value)?,
+                None => return Err(PyValueError::new_err(format!("Invalid evaluation weight {:?}", term)))
             }
         }
-
-        // Check if en-passant moves are available
-        // if (player === 'white' && y === 3) {
-          // let enPassantLeft = x - 1
-          // let enPassantRight = x + 1
-          // if (enPassantLeft >= 0 && enPassant['black'][enPassantLeft]) {
-            // moves.push({x: enPassantLeft, y: y -  1})
-          // }
-          // if (enPassantRight < COLS && enPassant['black'][enPassantRight]) {
-            // moves.push({x: enPassantRight, y: y -  1})
-          // }
-        // } else if (player === 'black' && y === 4) {
-          // let enPassantLeft = x - 1
-          // let enPassantRight = x + 1
-          // if (enPassantLeft >= 0 && enPassant['white'][enPassantLeft]) {
-            // moves.push({x: enPassantLeft, y: y +  1})
-          // }
-          // if (enPassantRight < COLS && enPassant['white'][enPassantRight]) {
-            // moves.push({x: enPassantRight, y: y +  1})
-          // }
-        // }
-
-        return moves
     }
+    eval::set_weights(new_weights);
 
-    fn knight_moves(&self, board_state: &[[Square; COLS]; ROWS]) -> Vec<(usize, usize)> {
-      // Knight has eight possible moves, and he can jump over other figures
-      let mut new_x = 0;
-      let mut new_y = 0;
-      let mut moves = Vec::new();
-
-      if self.x < COLS - 2 && self.y < ROWS - 1 {
-        // We add this move to the list if the square is empty or contains a figure
-        // of the opponent
-        new_x = self.x + 2;
-        new_y = self.y + 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY || board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y))
-        }
-      }
-
-      if self.x < COLS - 2 && self.y > 0 {
-        new_x = self.x + 2;
-        new_y = self.y - 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY || board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-        }
-      }
-
-      if self.x >= 2 && self.y < ROWS - 1 {
-        new_x = self.x - 2;
-        new_y = self.y + 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY || board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-        }
-      }
+    // The positions searched so far were scored with the old weights
+    transposition::shared_table().clear();
+    Ok(())
+}
 
-      if self.x > 1  && self.y > 0 {
-        new_x = self.x - 2;
-        new_y = self.y - 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY || board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-        }
-      }
+#[pyfunction]
+fn set_hash_size(megabytes: usize) -> PyResult<()> {
+    // Resizes the transposition table the bot keeps between its moves,
+    // which also clears it
+    transposition::shared_table().resize(megabytes).map_err(PyValueError::new_err)
+}
 
-      if self.x < COLS - 1 && self.y < ROWS - 2 {
-        new_x = self.x + 1;
-        new_y = self.y + 2;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY || board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-        }
-      }
+#[pyfunction]
+fn clear_hash() -> PyResult<()> {
+    // Forgets all the positions searched so far, like before a new game
+    transposition::shared_table().clear();
+    Ok(())
+}
 
-      if self.x < COLS - 1 && self.y > 1 {
-        new_x = self.x + 1;
-        new_y = self.y - 2;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY || board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-        }
-      }
+#[pyfunction]
+fn is_in_check(board_description: Vec<HashMap<String, String>>, player: &str) -> PyResult<bool> {
+    let board = Board::new(&board_description).map_err(PyValueError::new_err)?;
+    Ok(board.is_in_check(parse_player(player)?))
+}
 
-      if self.x > 0 && self.y < ROWS - 2 {
-        new_x = self.x - 1;
-        new_y = self.y + 2;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY || board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-        }
-      }
+#[pyfunction]
+fn board_from_fen(py: Python, fen: &str) -> PyResult<PyObject> {
+    // Describes the position the same way the other functions expect it,
+    // as the squares of the board and the state of the game
+    let board = Board::from_fen(fen).map_err(PyValueError::new_err)?;
+    let response = PyDict::new(py);
+    response.set_item("board", board.description())?;
+    response.set_item("state", board.state.into_py(py))?;
+    Ok(response.into())
+}
 
-      if self.x > 0 && self.y > 1 {
-        new_x = self.x - 1;
-        new_y = self.y - 2;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY || board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-        }
-      }
+#[pyfunction(state = "None", position_history = "Vec::new()")]
+fn board_to_fen(board_description: Vec<HashMap<String, String>>, state: Option<&PyDict>, position_history: Vec<u64>) -> PyResult<String> {
+    let board = build_board(&board_description, state, position_history)?;
+    Ok(board.to_fen())
+}
 
-      return moves
+#[pyfunction]
+fn read_pgn(py: Python, pgn: &str) -> PyResult<Vec<PyObject>> {
+    // Reads every game in the text, together with the position at the end
+    // of its main line, described the same way `board_from_fen` does
+    let games = Game::read_all(pgn).map_err(PyValueError::new_err)?;
+    let mut response = Vec::new();
+    for game in games.iter() {
+        let dict = PyDict::new(py);
+        let tags = PyDict::new(py);
+        for (name, value) in game.tags.iter() {
+            tags.set_item(name, value)?;
+        }
+        dict.set_item("tags", tags)?;
+        dict.set_item("moves", game_moves_into_py(py, &mut game.start_position(), &game.moves))?;
+        dict.set_item("result", &game.result)?;
+
+        let board = game.board();
+        let mut position_history = board.recent_positions().to_vec();
+        position_history.push(board.position_key());
+        dict.set_item("board", board.description())?;
+        dict.set_item("state", board.state.into_py(py))?;
+        dict.set_item("position_history", position_history)?;
+        response.push(dict.into());
     }
+    Ok(response)
+}
 
-    fn bishop_moves(&self, board_state: &[[Square; COLS]; ROWS]) -> Vec<(usize, usize)> {
-      // The bishop has four possible move types
-      let mut new_x = self.x;
-      let mut new_y = self.y;
-      let mut moves = Vec::new();
-
-      while new_x < COLS - 1 && new_y < ROWS - 1 {
-        // If there's a figure in this square, and if it's the opponent's figure,
-        // we add this move to the moves list, else we just break
-        new_x += 1;
-        new_y += 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY {
-          moves.push((new_x, new_y));
-        } else if board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-          break;
-        } else {
-          break;
-        }
-      }
-
-      new_x = self.x;
-      new_y = self.y;
-      while new_x < COLS - 1 && new_y >= 1 {
-        // If there's a figure in this square, and if it's the opponent's figure,
-        // we add this move to the moves list, else we just break
-        new_x += 1;
-        new_y -= 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY {
-          moves.push((new_x, new_y));
-        } else if board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-          break;
-        } else {
-          break;
-        }
-      }
-
-      new_x = self.x;
-      new_y = self.y;
-      while new_x >= 1 && new_y < ROWS - 1 {
-        // If there's a figure in this square, and if it's the opponent's figure,
-        // we add this move to the moves list, else we just break
-        new_x -= 1;
-        new_y += 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY {
-          moves.push((new_x, new_y));
-        } else if board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-          break;
-        } else {
-          break;
-        }
-      }
-
-      new_x = self.x;
-      new_y = self.y;
-      while new_x >= 1 && new_y >= 1 {
-        // If there's a figure in this square, and if it's the opponent's figure,
-        // we add this move to the moves list, else we just break
-        new_x -= 1;
-        new_y -= 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY {
-          moves.push((new_x, new_y));
-        } else if board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-          break;
-        } else {
-          break;
-        }
-      }
+#[pyfunction(tags = "HashMap::new()", fen = "None")]
+fn write_pgn(moves: Vec<Move>, tags: HashMap<String, String>, fen: Option<&str>) -> PyResult<String> {
+    // Writes the game made of the moves, played from the position in the FEN
+    // or the usual starting position; the moves are dicts like the ones the
+    // other functions return, of which only "from", "to" and "promotion"
+    // have to be there, or UCI strings
+    let start = Board::from_fen(fen.unwrap_or("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")).map_err(PyValueError::new_err)?;
+    let mut board = start.clone();
+    let mut played = Vec::new();
+    for mv in moves.iter() {
+        let legal = board.legal_moves().into_iter()
+            .find(|legal| legal.from == mv.from && legal.to == mv.to && legal.promotion == mv.promotion)
+            .ok_or_else(|| PyValueError::new_err(format!("Illegal move from {:?} to {:?} in position {}", mv.from, mv.to, board.to_fen())))?;
+        board.make_move(&legal);
+        played.push(legal);
+    }
 
-      return moves
+    let mut game = Game::from_moves(&start, &played);
+    for (name, value) in tags.iter() {
+        game.set_tag(name, value);
     }
+    Ok(game.to_pgn())
+}
 
-    fn rook_moves(&self, board_state: &[[Square; COLS]; ROWS]) -> Vec<(usize, usize)> {
-      // The rook has four possible move types
-      let mut new_x = self.x;
-      let mut new_y = self.y;
-      let mut moves = Vec::new();
-
-      while new_x < COLS - 1 {
-        // If there's a figure in this square, and if it's the opponent's figure,
-        // we add this move to the moves list, else we just break
-        new_x += 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY {
-          moves.push((new_x, new_y));
-        } else if board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-          break;
-        } else {
-          break;
-        }
-      }
-
-      new_x = self.x;
-      new_y = self.y;
-      while new_x >= 1 {
-        // If there's a figure in this square, and if it's the opponent's figure,
-        // we add this move to the moves list, else we just break
-        new_x -= 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY {
-          moves.push((new_x, new_y));
-        } else if board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-          break;
-        } else {
-          break;
-        }
-      }
-
-      new_x = self.x;
-      new_y = self.y;
-      while new_y < ROWS - 1 {
-        // If there's a figure in this square, and if it's the opponent's figure,
-        // we add this move to the moves list, else we just break
-        new_y += 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY {
-          moves.push((new_x, new_y));
-        } else if board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-          break;
-        } else {
-          break;
-        }
-      }
-
-      new_x = self.x;
-      new_y = self.y;
-      while new_y >= 1 {
-        // If there's a figure in this square, and if it's the opponent's figure,
-        // we add this move to the moves list, else we just break
-        new_y -= 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY {
-          moves.push((new_x, new_y));
-        } else if board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-          break;
-        } else {
-          break;
-        }
-      }
+fn move_with_san_into_py(py: Python, mv: &Move, board: &Board) -> PyObject {
+    // The move, as well as how chess players would write it down; the move
+    // has to be legal on the board
+    let dict = mv.into_py(py);
+    dict.as_ref(py).set_item("san", mv.to_san(board)).unwrap();
+    return dict
+}
+
+fn score_into_py(py: Python, score: Score) -> PyObject {
+    return (score.middlegame, score.endgame).into_py(py)
+}
 
-      return moves
+fn extract_score(value: &PyAny) -> PyResult<Score> {
+    // A pair of middlegame and endgame scores, as a tuple or a list
+    let score: Vec<i32> = value.extract()?;
+    match score[..] {
+        [middlegame, endgame] => Ok(Score::new(middlegame, endgame)),
+        _ => Err(PyValueError::new_err(format!("Expected a middlegame and an endgame score, got {:?}", score)))
     }
+}
 
-    fn queen_moves(&self, board_state: &[[Square; COLS]; ROWS]) -> Vec<(usize, usize)> {
-      // The queen has four possible move types
-      let mut new_x = self.x;
-      let mut new_y = self.y;
-      let mut moves = Vec::new();
-
-      while new_x < COLS - 1 && new_y < ROWS - 1 {
-        // If there's a figure in this square, and if it's the opponent's figure,
-        // we add this move to the moves list, else we just break
-        new_x += 1;
-        new_y += 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY {
-          moves.push((new_x, new_y))
-        } else if board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-          break;
-        } else {
-          break;
-        }
-      }
-
-      new_x = self.x;
-      new_y = self.y;
-      while new_x < COLS - 1 && new_y >= 1 {
-        // If there's a figure in this square, and if it's the opponent's figure,
-        // we add this move to the moves list, else we just break
-        new_x += 1;
-        new_y -= 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY {
-          moves.push((new_x, new_y))
-        } else if board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-          break;
-        } else {
-          break;
-        }
-      }
-
-      new_x = self.x;
-      new_y = self.y;
-      while new_x >= 1 && new_y < ROWS - 1 {
-        // If there's a figure in this square, and if it's the opponent's figure,
-        // we add this move to the moves list, else we just break
-        new_x -= 1;
-        new_y += 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY {
-          moves.push((new_x, new_y))
-        } else if board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-          break;
-        } else {
-          break;
-        }
-      }
-
-      new_x = self.x;
-      new_y = self.y;
-      while new_x >= 1 && new_y >= 1 {
-        // If there's a figure in this square, and if it's the opponent's figure,
-        // we add this move to the moves list, else we just break
-        new_x -= 1;
-        new_y -= 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY {
-          moves.push((new_x, new_y))
-        } else if board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-          break;
-        } else {
-          break;
-        }
-      }
-
-      new_x = self.x;
-      new_y = self.y;
-      while new_x < COLS - 1 {
-        // If there's a figure in this square, and if it's the opponent's figure,
-        // we add this move to the moves list, else we just break
-        new_x += 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY {
-          moves.push((new_x, new_y))
-        } else if board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-          break;
-        } else {
-          break;
-        }
-      }
-
-      new_x = self.x;
-      new_y = self.y;
-      while new_x >= 1 {
-        // If there's a figure in this square, and if it's the opponent's figure,
-        // we add this move to the moves list, else we just break
-        new_x -= 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY {
-          moves.push((new_x, new_y))
-        } else if board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-          break;
-        } else {
-          break;
-        }
-      }
-
-      new_x = self.x;
-      new_y = self.y;
-      while new_y < ROWS - 1 {
-        // If there's a figure in this square, and if it's the opponent's figure,
-        // we add this move to the moves list, else we just break
-        new_y += 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY {
-          moves.push((new_x, new_y))
-        } else if board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-          break;
-        } else {
-          break;
+fn weights_into_py(py: Python, weights: &Weights) -> PyObject {
+    let dict = PyDict::new(py);
+    let mut terms = *weights;
+    for name in Weights::FIGURE_TERMS.iter() {
+        let scores = PyDict::new(py);
+        for figure in board::FIGURES.iter() {
+            scores.set_item(figure.name(), score_into_py(py, terms.figure_term(name).unwrap()[figure.index()])).unwrap();
         }
-      }
-
-      new_x = self.x;
-      new_y = self.y;
-      while new_y >= 1 {
-        // If there's a figure in this square, and if it's the opponent's figure,
-        // we add this move to the moves list, else we just break
-        new_y -= 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY {
-          moves.push((new_x, new_y))
-        } else if board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-          break;
-        } else {
-          break;
-        }
-      }
-
-      return moves
+        dict.set_item(*name, scores).unwrap();
     }
+    for name in Weights::TERMS.iter() {
+        dict.set_item(*name, score_into_py(py, *terms.term(name).unwrap())).unwrap();
+    }
+    let passed_pawn: Vec<PyObject> = weights.passed_pawn.iter().map(|score| score_into_py(py, *score)).collect();
+    dict.set_item("passed_pawn", passed_pawn).unwrap();
+    return dict.into()
+}
 
-    fn king_moves(&self, board_state: &[[Square; COLS]; ROWS]) -> Vec<(usize, usize)> {
-      // The king has eight normal moves and two castling moves
-      let mut new_x = self.x;
-      let mut new_y = self.y;
-      let mut moves = Vec::new();
-
-      if self.y < ROWS - 1 {
-        new_x = self.x;
-        new_y = self.y + 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY || board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-        }
-      }
+fn search_result_into_py(py: Python, result: &SearchResult, board: &mut Board) -> PyObject {
+    // The moves of the principal variation are played out on the board to
+    // get their SAN, and taken back afterwards
+    let dict = PyDict::new(py);
+    dict.set_item("move", result.best_move.map(|mv| move_with_san_into_py(py, &mv, board))).unwrap();
+    dict.set_item("score", result.score).unwrap();
+    dict.set_item("mate_in", result.mate_in()).unwrap();
+    let mut principal_variation = Vec::new();
+    for mv in result.principal_variation.iter() {
+        principal_variation.push(move_with_san_into_py(py, mv, board));
+        board.make_move(mv);
+    }
+    for _ in result.principal_variation.iter() {
+        board.unmake_move();
+    }
+    dict.set_item("principal_variation", principal_variation).unwrap();
+    dict.set_item("depth", result.depth).unwrap();
+    dict.set_item("nodes", result.nodes).unwrap();
+    return dict.into()
+}
 
-      if self.y > 0 {
-        new_x = self.x;
-        new_y = self.y - 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY || board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-        }
-      }
+fn game_moves_into_py(py: Python, board: &mut Board, moves: &[GameMove]) -> PyObject {
+    // Every move along with its SAN, annotations and variations, which are
+    // played from the position before the move
+    let mut list = Vec::new();
+    for game_move in moves.iter() {
+        let dict = PyDict::new(py);
+        dict.set_item("move", move_with_san_into_py(py, &game_move.mv, board)).unwrap();
+        dict.set_item("comment_before", &game_move.comment_before).unwrap();
+        dict.set_item("comment", &game_move.comment).unwrap();
+        dict.set_item("nags", &game_move.nags).unwrap();
+        let variations: Vec<PyObject> = game_move.variations.iter()
+            .map(|variation| game_moves_into_py(py, board, variation))
+            .collect();
+        dict.set_item("variations", variations).unwrap();
+        list.push(dict);
+        board.make_move(&game_move.mv);
+    }
+    for _ in moves.iter() {
+        board.unmake_move();
+    }
+    return list.into_py(py)
+}
 
-      if self.x < COLS - 1 {
-        new_x = self.x + 1;
-        new_y = self.y;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY || board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-        }
-      }
+/// A Python module implemented in Rust
+#[pymodule]
+fn plakychess(_py: Python, m: &PyModule) -> PyResult<()> {
+    magic::init();
 
-      if self.x > 0 {
-        new_x = self.x - 1;
-        new_y = self.y;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY || board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-        }
-      }
+    m.add_function(wrap_pyfunction!(get_next_move, m)?)?;
+    m.add_function(wrap_pyfunction!(get_available_moves, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_san, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_uci_move, m)?)?;
+    m.add_function(wrap_pyfunction!(make_move, m)?)?;
+    m.add_function(wrap_pyfunction!(square_name, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_square, m)?)?;
+    m.add_function(wrap_pyfunction!(get_game_status, m)?)?;
+    m.add_function(wrap_pyfunction!(search_position, m)?)?;
+    m.add_function(wrap_pyfunction!(evaluate_position, m)?)?;
+    m.add_function(wrap_pyfunction!(get_eval_weights, m)?)?;
+    m.add_function(wrap_pyfunction!(set_eval_weights, m)?)?;
+    m.add_function(wrap_pyfunction!(set_hash_size, m)?)?;
+    m.add_function(wrap_pyfunction!(clear_hash, m)?)?;
+    m.add_function(wrap_pyfunction!(is_in_check, m)?)?;
+    m.add_function(wrap_pyfunction!(board_from_fen, m)?)?;
+    m.add_function(wrap_pyfunction!(board_to_fen, m)?)?;
+    m.add_function(wrap_pyfunction!(read_pgn, m)?)?;
+    m.add_function(wrap_pyfunction!(write_pgn, m)?)?;
+    Ok(())
+}
 
-      if self.x < COLS - 1 && self.y < ROWS - 1 {
-        new_x = self.x + 1;
-        new_y = self.y + 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY || board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-        }
-      }
+impl IntoPy<PyObject> for Move {
+    fn into_py(self, py: Python) -> PyObject {
+        // Moves reach Python as plain dicts, so they can be sent on to the
+        // frontend as JSON without any further conversion
+        let dict = PyDict::new(py);
+        dict.set_item("from", self.from).unwrap();
+        dict.set_item("to", self.to).unwrap();
+        dict.set_item("promotion", self.promotion.map(|figure| figure.name())).unwrap();
+        dict.set_item("capture", self.is_capture()).unwrap();
+        dict.set_item("double_pawn_push", self.is_double_pawn_push()).unwrap();
+        dict.set_item("en_passant", self.is_en_passant()).unwrap();
+        dict.set_item("castling", self.is_castling()).unwrap();
+        dict.set_item("uci", self.to_uci()).unwrap();
+        return dict.into()
+    }
+}
 
-      if self.x < COLS - 1 && self.y > 0 {
-        new_x = self.x + 1;
-        new_y = self.y - 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY || board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-        }
-      }
+impl<'a> FromPyObject<'a> for Move {
+    fn extract(object: &'a PyAny) -> PyResult<Self> {
+        // The inverse of the conversion above, though only where the figure
+        // moves from and to, and what it's promoted to, are read; the rest
+        // depends on the position the move is played in. UCI strings like
+        // "e7e8q" are accepted as well
+        if let Ok(uci) = object.extract::<&str>() {
+            let (from, to, promotion) = uci::parse_uci(uci)
+                .ok_or_else(|| PyValueError::new_err(format!("Invalid UCI move {:?}", uci)))?;
+            return Ok(Move { promotion, ..Move::new(from, to) })
+        }
+        let promotion: Option<&str> = match object.get_item("promotion") {
+            Ok(promotion) => promotion.extract()?,
+            Err(_) => None
+        };
+        return Ok(Move {
+            promotion: promotion.map(parse_figure).transpose()?,
+            ..Move::new(object.get_item("from")?.extract()?, object.get_item("to")?.extract()?)
+        })
+    }
+}
 
-      if self.x > 0 && self.y < ROWS - 1 {
-        new_x = self.x - 1;
-        new_y = self.y + 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY || board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
+impl IntoPy<PyObject> for GameStatus {
+    fn into_py(self, py: Python) -> PyObject {
+        let dict = PyDict::new(py);
+        match self {
+            GameStatus::ONGOING => dict.set_item("status", "ONGOING").unwrap(),
+            GameStatus::CHECKMATE(winner) => {
+                dict.set_item("status", "CHECKMATE").unwrap();
+                dict.set_item("winner", winner.name()).unwrap();
+            },
+            GameStatus::STALEMATE => dict.set_item("status", "STALEMATE").unwrap(),
+            GameStatus::DRAW(reason) => {
+                dict.set_item("status", "DRAW").unwrap();
+                dict.set_item("reason", reason.name()).unwrap();
+            }
         }
-      }
+        return dict.into()
+    }
+}
 
-      if self.x > 0 && self.y > 0 {
-        new_x = self.x - 1;
-        new_y = self.y - 1;
-        if board_state[new_y][new_x].content == SquareContent::EMPTY || board_state[new_y][new_x].belongs_to != self.belongs_to {
-          moves.push((new_x, new_y));
-        }
-      }
-
-      // Now we check for castling related moves
-      // if (player === 'white' && castlingWhiteLeft) {
-        // // Check if there are any figures blocking the way
-        // if (!board[7][2].figure && !board[7][3].figure) {
-
-          // // Check if any of the fields the king is moving over are under attack
-          // if (!isUnderAttack('black', [{y: 7, x: 2}, {y: 7, x: 3}], board)) {
-            // moves.push({x: 2, y: 7})
-          // }
-        // }
-      // }
-      // if (player === 'white' && castlingWhiteRight) {
-        // // Check if there are any figures blocking the way
-        // if (!board[7][5].figure && !board[7][6].figure) {
-
-          // // Check if any of the fields the king is moving over are under attack
-          // if (!isUnderAttack('black', [{y: 7, x: 5}, {y: 7, x: 6}], board)) {
-            // moves.push({x: 6, y: 7})
-          // }
-        // }
-      // }
-      // if (player === 'black' && castlingBlackLeft) {
-        // // Check if there are any figures blocking the way
-        // if (!board[0][2].figure && !board[0][3].figure) {
-
-          // // Check if any of the fields the king is moving over are under attack
-          // if (!isUnderAttack('white', [{y: 0, x: 2}, {y: 0, x: 3}], board)) {
-            // moves.push({x: 2, y: 0})
-          // }
-        // }
-      // }
-      // if (player === 'black' && castlingBlackRight) {
-        // // Check if there are any figures blocking the way
-        // if (!board[0][5].figure && !board[0][6].figure) {
-
-          // // Check if any of the fields the king is moving over are under attack
-          // if (!isUnderAttack('white', [{y: 0, x: 5}, {y: 0, x: 6}], board)) {
-            // moves.push({x: 6, y: 0})
-          // }
-        // }
-      // }
-
-      return moves
+impl IntoPy<PyObject> for GameState {
+    fn into_py(self, py: Python) -> PyObject {
+        // Uses the same names and formats as the state the Python functions
+        // take, so it can be passed straight back in
+        let dict = PyDict::new(py);
+        dict.set_item("side_to_move", self.side_to_move.name()).unwrap();
+        dict.set_item("castling_rights", self.castling_rights.description()).unwrap();
+        dict.set_item("en_passant", self.en_passant).unwrap();
+        dict.set_item("halfmove_clock", self.halfmove_clock).unwrap();
+        dict.set_item("fullmove_number", self.fullmove_number).unwrap();
+        return dict.into()
     }
 }
@@ -0,0 +1,143 @@
+use std::sync::OnceLock;
+
+use crate::bitboard::{BISHOP_DIRECTIONS, Bitboard, COLS, ROOK_DIRECTIONS, ROWS, sliding_attacks};
+use crate::random::Xorshift;
+
+// Attacks of the sliding figures are looked up with magic bitboards: the
+// figures which can block a rook or a bishop on a given square are multiplied
+// by a number picked so that the top bits of the product are different for
+// every set of blockers which leads to different attacks, and those bits are
+// then used as an index into a table of precomputed attacks
+#[derive(Copy, Clone, Default)]
+struct Magic {
+    // Squares whose figures can block the slider; the last square of every
+    // ray never blocks anything behind it, so it's left out
+    mask: Bitboard,
+    magic: u64,
+    shift: u32,
+    // Where the attacks for this square start in the shared table
+    offset: usize
+}
+
+impl Magic {
+    fn index(&self, occupied: Bitboard) -> usize {
+        return self.offset + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+struct SlidingAttacks {
+    rook: [Magic; 64],
+    bishop: [Magic; 64],
+    attacks: Vec<Bitboard>
+}
+
+static SLIDING_ATTACKS: OnceLock<SlidingAttacks> = OnceLock::new();
+
+fn sliding_attack_tables() -> &'static SlidingAttacks {
+    return SLIDING_ATTACKS.get_or_init(|| {
+        let mut tables = SlidingAttacks { rook: [Magic::default(); 64], bishop: [Magic::default(); 64], attacks: Vec::new() };
+        let mut random = Xorshift::new(0x9e3779b97f4a7c15);
+        for square in 0..64 {
+            tables.rook[square] = find_magic(square, &ROOK_DIRECTIONS, &mut tables.attacks, &mut random);
+            tables.bishop[square] = find_magic(square, &BISHOP_DIRECTIONS, &mut tables.attacks, &mut random);
+        }
+        return tables
+    })
+}
+
+pub fn init() {
+    // Building the tables takes a moment, which is better spent up front than
+    // on the first move the bot has to find
+    sliding_attack_tables();
+}
+
+pub fn rook_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    let tables = sliding_attack_tables();
+    return tables.attacks[tables.rook[square].index(occupied)]
+}
+
+pub fn bishop_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    let tables = sliding_attack_tables();
+    return tables.attacks[tables.bishop[square].index(occupied)]
+}
+
+pub fn queen_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    return rook_attacks(square, occupied) | bishop_attacks(square, occupied)
+}
+
+fn blocker_mask(square: usize, directions: &[(i32, i32); 4]) -> Bitboard {
+    // The attacks on an empty board, without the edges of the board, unless
+    // the slider itself stands on that edge and moves along it
+    let (x, y) = (square % COLS, square / COLS);
+    let first_row: Bitboard = 0xff;
+    let last_row = first_row << ((ROWS - 1) * COLS);
+    let first_column: Bitboard = 0x0101010101010101;
+    let last_column = first_column << (COLS - 1);
+
+    let mut edges = 0;
+    if y != 0 {
+        edges |= first_row;
+    }
+    if y != ROWS - 1 {
+        edges |= last_row;
+    }
+    if x != 0 {
+        edges |= first_column;
+    }
+    if x != COLS - 1 {
+        edges |= last_column;
+    }
+    return sliding_attacks(square, 0, directions) & !edges
+}
+
+fn find_magic(square: usize, directions: &[(i32, i32); 4], attacks: &mut Vec<Bitboard>, random: &mut Xorshift) -> Magic {
+    // Tries random numbers until one maps every set of blockers to a slot of
+    // the table which no set with different attacks maps to
+    let mask = blocker_mask(square, directions);
+    let bits = mask.count_ones();
+
+    // Walk through every subset of the mask, using the carry-rippler trick
+    let mut blockers = Vec::new();
+    let mut reference = Vec::new();
+    let mut subset: Bitboard = 0;
+    loop {
+        blockers.push(subset);
+        reference.push(sliding_attacks(square, subset, directions));
+        subset = subset.wrapping_sub(mask) & mask;
+        if subset == 0 {
+            break;
+        }
+    }
+
+    let size = 1 << bits;
+    let mut table = vec![0; size];
+    let mut filled_in = vec![0; size];
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        // Numbers with few bits set make good magics
+        let magic = random.next() & random.next() & random.next();
+        if (mask.wrapping_mul(magic) >> 56).count_ones() < 6 {
+            continue;
+        }
+
+        let candidate = Magic { mask, magic, shift: 64 - bits, offset: 0 };
+        let mut collision = false;
+        for (subset, subset_attacks) in blockers.iter().zip(reference.iter()) {
+            let index = candidate.index(*subset);
+            if filled_in[index] != attempt {
+                filled_in[index] = attempt;
+                table[index] = *subset_attacks;
+            } else if table[index] != *subset_attacks {
+                collision = true;
+                break;
+            }
+        }
+
+        if !collision {
+            let offset = attacks.len();
+            attacks.extend(table);
+            return Magic { offset, ..candidate }
+        }
+    }
+}
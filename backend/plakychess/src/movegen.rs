@@ -0,0 +1,337 @@
+use crate::bitboard::{Bitboard, COLS, KING_ATTACKS, KNIGHT_ATTACKS, PAWN_ATTACKS, ROWS, bit, square_coords, square_index, squares};
+use crate::board::{Board, PlayerName, SquareContent};
+use crate::magic::{bishop_attacks, queen_attacks, rook_attacks};
+
+// The figures a pawn can be promoted to, the most useful one first
+pub const PROMOTIONS: [SquareContent; 4] = [SquareContent::QUEEN, SquareContent::ROOK, SquareContent::BISHOP, SquareContent::KNIGHT];
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Move {
+    pub from: (usize, usize),
+    pub to: (usize, usize),
+
+    // The figure a pawn turns into when it reaches the last row
+    pub promotion: Option<SquareContent>,
+
+    // Combination of the flags below, describing what else the move does
+    // besides moving a figure from one square to another
+    pub flags: u8
+}
+
+impl Move {
+    pub const CAPTURE: u8 = 1;
+    pub const DOUBLE_PAWN_PUSH: u8 = 2;
+    pub const EN_PASSANT: u8 = 4;
+    pub const CASTLING: u8 = 8;
+
+    pub fn new(from: (usize, usize), to: (usize, usize)) -> Self {
+        return Move { from, to, promotion: None, flags: 0 }
+    }
+
+    pub fn is_capture(&self) -> bool {
+        return self.flags & Move::CAPTURE != 0
+    }
+
+    pub fn is_double_pawn_push(&self) -> bool {
+        return self.flags & Move::DOUBLE_PAWN_PUSH != 0
+    }
+
+    pub fn is_en_passant(&self) -> bool {
+        return self.flags & Move::EN_PASSANT != 0
+    }
+
+    pub fn is_castling(&self) -> bool {
+        return self.flags & Move::CASTLING != 0
+    }
+}
+
+pub(crate) fn attackers_to(pieces: &[[Bitboard; 6]; 2], occupied: Bitboard, square: usize, by: PlayerName) -> Bitboard {
+    // Every figure attacks exactly the squares from which the same kind of
+    // figure of the other player would attack it back, so the attackers can
+    // be found by looking outwards from the square. Pawns count even when the
+    // square is empty and they couldn't actually move there
+    let attacker = &pieces[by.index()];
+    let bishops = attacker[SquareContent::BISHOP.index()] | attacker[SquareContent::QUEEN.index()];
+    let rooks = attacker[SquareContent::ROOK.index()] | attacker[SquareContent::QUEEN.index()];
+    return PAWN_ATTACKS[by.opponent().index()][square] & attacker[SquareContent::PAWN.index()]
+        | KNIGHT_ATTACKS[square] & attacker[SquareContent::KNIGHT.index()]
+        | KING_ATTACKS[square] & attacker[SquareContent::KING.index()]
+        | bishop_attacks(square, occupied) & bishops
+        | rook_attacks(square, occupied) & rooks
+}
+
+impl Board {
+    pub fn get_moves(&self, x: usize, y: usize) -> Vec<Move> {
+        // Only the moves which don't leave the player's own king in check
+        let (_, player) = self.figure_at((x, y));
+        if player == PlayerName::NEITHER {
+            return Vec::new()
+        }
+        let mut moves = self.generate_moves(player, bit((x, y)));
+        moves.retain(|mv| self.is_legal(mv, player));
+        return moves
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        return self.get_available_moves(self.state.side_to_move)
+    }
+
+    pub fn get_available_moves(&self, player: PlayerName) -> Vec<Move> {
+        let mut moves = self.generate_moves(player, !0);
+        moves.retain(|mv| self.is_legal(mv, player));
+        return moves
+    }
+
+    fn is_legal(&self, mv: &Move, player: PlayerName) -> bool {
+        // Plays the move out on copies of the bitboards, and checks whether
+        // the opponent can take the king afterwards; only which squares are
+        // taken matters for that, not which of the player's figures took them
+        let mut pieces = self.pieces;
+        let own = &mut pieces[player.index()];
+        let (moving, _) = self.figure_at(mv.from);
+        own[moving.index()] ^= bit(mv.from) | bit(mv.to);
+        if mv.is_castling() {
+            let (rook_from_x, rook_to_x) = if mv.to.0 > mv.from.0 { (7, 5) } else { (0, 3) };
+            own[SquareContent::ROOK.index()] ^= bit((rook_from_x, mv.from.1)) | bit((rook_to_x, mv.from.1));
+        }
+
+        let captured = if mv.is_en_passant() { bit((mv.to.0, mv.from.1)) } else { bit(mv.to) };
+        for figure in pieces[player.opponent().index()].iter_mut() {
+            *figure &= !captured;
+        }
+
+        let king = pieces[player.index()][SquareContent::KING.index()];
+        if king == 0 {
+            // Without a king there's nothing to leave in check
+            return true
+        }
+        let occupied = pieces.iter().flatten().fold(0, |occupied, figure| occupied | figure);
+        return attackers_to(&pieces, occupied, king.trailing_zeros() as usize, player.opponent()) == 0
+    }
+
+    fn generate_moves(&self, player: PlayerName, from_mask: Bitboard) -> Vec<Move> {
+        // All moves of the player's figures standing on the squares in the
+        // mask, including the ones which leave the player's king in check
+        let mut moves = Vec::new();
+        let us = player.index();
+        let own = self.occupied[us];
+        let enemy = self.occupied[player.opponent().index()];
+        let occupied = own | enemy;
+        let figures = |content: SquareContent| self.pieces[us][content.index()] & from_mask;
+
+        self.generate_pawn_moves(player, figures(SquareContent::PAWN), &mut moves);
+
+        // Every other figure can move onto any square it attacks, unless one
+        // of the player's own figures is already there
+        for from in squares(figures(SquareContent::KNIGHT)) {
+            add_moves(from, KNIGHT_ATTACKS[from] & !own, enemy, &mut moves);
+        }
+        for from in squares(figures(SquareContent::BISHOP)) {
+            add_moves(from, bishop_attacks(from, occupied) & !own, enemy, &mut moves);
+        }
+        for from in squares(figures(SquareContent::ROOK)) {
+            add_moves(from, rook_attacks(from, occupied) & !own, enemy, &mut moves);
+        }
+        for from in squares(figures(SquareContent::QUEEN)) {
+            add_moves(from, queen_attacks(from, occupied) & !own, enemy, &mut moves);
+        }
+        for from in squares(figures(SquareContent::KING)) {
+            add_moves(from, KING_ATTACKS[from] & !own, enemy, &mut moves);
+            self.generate_castling_moves(player, from, &mut moves);
+        }
+
+        return moves
+    }
+
+    fn generate_pawn_moves(&self, player: PlayerName, pawns: Bitboard, moves: &mut Vec<Move>) {
+        // Pawns move one square forward onto an empty square, or two from
+        // their starting row if both squares are empty, and capture one
+        // square diagonally forward, including en passant
+        let occupied = self.all_occupied();
+        let enemy = self.occupied[player.opponent().index()];
+        let (forward, start_row) = if player == PlayerName::WHITE { (-1, ROWS - 2) } else { (1, 1) };
+
+        for from in squares(pawns) {
+            let (x, y) = square_coords(from);
+            let one_step = square_index((x, (y as i32 + forward) as usize));
+            if occupied & (1 << one_step) == 0 {
+                add_pawn_move(from, one_step, 0, moves);
+
+                if y == start_row {
+                    let two_steps = square_index((x, (y as i32 + 2 * forward) as usize));
+                    if occupied & (1 << two_steps) == 0 {
+                        add_pawn_move(from, two_steps, Move::DOUBLE_PAWN_PUSH, moves);
+                    }
+                }
+            }
+
+            let attacks = PAWN_ATTACKS[player.index()][from];
+            for to in squares(attacks & enemy) {
+                add_pawn_move(from, to, Move::CAPTURE, moves);
+            }
+            // The en passant square may come from a FEN or from Python, so
+            // it's only taken when it's empty and the pawn which skipped it
+            // stands beside this one
+            if let Some(en_passant) = self.state.en_passant {
+                let pushed_pawn = bit((en_passant.0, y)) & self.pieces[player.opponent().index()][SquareContent::PAWN.index()];
+                if attacks & bit(en_passant) != 0 && occupied & bit(en_passant) == 0 && pushed_pawn != 0 {
+                    add_pawn_move(from, square_index(en_passant), Move::CAPTURE | Move::EN_PASSANT, moves);
+                }
+            }
+        }
+    }
+
+    fn generate_castling_moves(&self, player: PlayerName, king: usize, moves: &mut Vec<Move>) {
+        // The king and the rook have to be on their starting squares, with
+        // nothing between them. The castling rights keep track of whether
+        // they ever moved, but rights which came from a FEN or from Python
+        // may not match the board, so the rook is looked for as well. The
+        // king can't castle out of, or through check, nor into it
+        let rights = self.state.castling_rights;
+        let (row, king_side, queen_side) = if player == PlayerName::WHITE {
+            (ROWS - 1, rights.white_king_side, rights.white_queen_side)
+        } else {
+            (0, rights.black_king_side, rights.black_queen_side)
+        };
+        if king != square_index((4, row)) {
+            return
+        }
+
+        let occupied = self.all_occupied();
+        let is_safe = |squares: &[usize]| {
+            squares.iter().all(|x| attackers_to(&self.pieces, occupied, square_index((*x, row)), player.opponent()) == 0)
+        };
+        let is_empty = |squares: &[usize]| squares.iter().all(|x| occupied & bit((*x, row)) == 0);
+        let has_rook = |x: usize| self.pieces[player.index()][SquareContent::ROOK.index()] & bit((x, row)) != 0;
+
+        if king_side && has_rook(COLS - 1) && is_empty(&[5, 6]) && is_safe(&[4, 5, 6]) {
+            moves.push(Move { flags: Move::CASTLING, ..Move::new((4, row), (6, row)) });
+        }
+
+        // On this side the rook also moves over a square the king never touches
+        if queen_side && has_rook(0) && is_empty(&[1, 2, 3]) && is_safe(&[4, 3, 2]) {
+            moves.push(Move { flags: Move::CASTLING, ..Move::new((4, row), (2, row)) });
+        }
+    }
+}
+
+fn add_moves(from: usize, targets: Bitboard, enemy: Bitboard, moves: &mut Vec<Move>) {
+    for to in squares(targets) {
+        let mut mv = Move::new(square_coords(from), square_coords(to));
+        if enemy & (1 << to) != 0 {
+            mv.flags |= Move::CAPTURE;
+        }
+        moves.push(mv);
+    }
+}
+
+fn add_pawn_move(from: usize, to: usize, flags: u8, moves: &mut Vec<Move>) {
+    // A pawn reaching the last row has to be promoted, so there's one move
+    // for every figure it can turn into
+    let mv = Move { flags, ..Move::new(square_coords(from), square_coords(to)) };
+    let (_, row) = mv.to;
+    if row == 0 || row == ROWS - 1 {
+        for promotion in PROMOTIONS.iter() {
+            moves.push(Move { promotion: Some(*promotion), ..mv });
+        }
+    } else {
+        moves.push(mv);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+
+    fn legal_uci(fen: &str) -> Vec<String> {
+        return Board::from_fen(fen).unwrap().legal_moves().iter().map(|mv| mv.to_uci()).collect()
+    }
+
+    #[test]
+    fn castling_needs_the_rook() {
+        // The rights say white can castle, but there's no rook to castle with
+        assert!(!legal_uci("4k3/8/8/8/8/8/8/4K3 w KQ - 0 1").iter().any(|mv| mv == "e1g1" || mv == "e1c1"));
+        // Nor with some other figure in the corner
+        assert!(!legal_uci("4k3/8/8/8/8/8/8/n3K2n w KQ - 0 1").iter().any(|mv| mv == "e1g1" || mv == "e1c1"));
+
+        let moves = legal_uci("r3k3/8/8/8/8/8/8/R3K2R b KQq - 0 1");
+        assert!(moves.contains(&"e8c8".to_string()));
+        let moves = legal_uci("r3k3/8/8/8/8/8/8/R3K2R w KQq - 0 1");
+        assert!(moves.contains(&"e1g1".to_string()) && moves.contains(&"e1c1".to_string()));
+    }
+
+    #[test]
+    fn en_passant() {
+        let board = |fen: &str| Board::from_fen(fen).unwrap();
+        let find = |board: &Board, uci: &str| board.legal_moves().into_iter().filter(|mv| mv.to_uci() == uci).collect::<Vec<_>>();
+
+        // The pawn which skipped the square is taken, from beside the pawn
+        // which takes it, by either side
+        for (fen, uci, after) in [
+            ("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1", "d5e6", "4k3/8/4P3/8/8/8/8/4K3 b - - 0 1"),
+            ("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1", "d4e3", "4k3/8/8/8/8/4p3/8/4K3 w - - 0 2")
+        ].iter() {
+            let mut board = board(fen);
+            let moves = find(&board, uci);
+            assert!(moves.len() == 1 && moves[0].is_en_passant());
+            board.make_move(&moves[0]);
+            assert_eq!(board.to_fen(), *after);
+            board.unmake_move();
+            assert_eq!(board.to_fen(), *fen);
+        }
+
+        // Only right after the double push
+        assert!(find(&board("4k3/8/8/3Pp3/8/8/8/4K3 w - - 0 1"), "d5e6").is_empty());
+        // Nor when something other than a pawn stands beside the capturing
+        // one, or the square isn't empty, which can't happen in a game but
+        // can in a FEN
+        assert!(find(&board("4k3/8/8/3Pn3/8/8/8/4K3 w - e6 0 1"), "d5e6").is_empty());
+        let moves = find(&board("4k3/8/4n3/3Pp3/8/8/8/4K3 w - e6 0 1"), "d5e6");
+        assert!(moves.len() == 1 && !moves[0].is_en_passant());
+        // Nor when taking both pawns off the row leaves the king in check
+        assert!(find(&board("8/8/8/K2pP2r/8/8/8/7k w - d6 0 1"), "e5d6").is_empty());
+    }
+
+    #[test]
+    fn castling() {
+        let castles = |fen: &str| {
+            let moves = legal_uci(fen);
+            return (moves.contains(&"e1g1".to_string()), moves.contains(&"e1c1".to_string()))
+        };
+        assert_eq!(castles("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1"), (true, true));
+        // Only to the sides the rights allow
+        assert_eq!(castles("4k3/8/8/8/8/8/8/R3K2R w Q - 0 1"), (false, true));
+        assert_eq!(castles("4k3/8/8/8/8/8/8/R3K2R w - - 0 1"), (false, false));
+        // Not with anything in between
+        assert_eq!(castles("4k3/8/8/8/8/8/8/RN2K2R w KQ - 0 1"), (true, false));
+        // Not out of check, nor through or into it, though the rook may
+        // pass an attacked square
+        assert_eq!(castles("4k3/8/8/8/8/8/4r3/R3K2R w KQ - 0 1"), (false, false));
+        assert_eq!(castles("4kr2/8/8/8/8/8/8/R3K2R w KQ - 0 1"), (false, true));
+        assert_eq!(castles("4k1r1/8/8/8/8/8/8/R3K2R w KQ - 0 1"), (false, true));
+        assert_eq!(castles("1r2k3/8/8/8/8/8/8/R3K2R w KQ - 0 1"), (true, true));
+
+        // The rook jumps over the king, and the rights go away once the king
+        // or the rook moves
+        let mut board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        for (uci, after) in [
+            ("e1g1", "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1"),
+            ("a8b8", "1r2k2r/8/8/8/8/8/8/R4RK1 w k - 2 2"),
+            ("f1f8", "1r2kR1r/8/8/8/8/8/8/R5K1 b k - 3 2"),
+            ("e8f8", "1r3k1r/8/8/8/8/8/8/R5K1 w - - 0 3")
+        ].iter() {
+            let mv = board.parse_uci_move(uci).unwrap();
+            board.make_move(&mv);
+            assert_eq!(board.to_fen(), *after);
+        }
+        for _ in 0..4 {
+            board.unmake_move();
+        }
+        assert_eq!(board.to_fen(), "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+        // Or once the rook is taken
+        let mv = board.parse_uci_move("a1a8").unwrap();
+        board.make_move(&mv);
+        assert_eq!(board.to_fen(), "R3k2r/8/8/8/8/8/8/4K2R b Kk - 0 1");
+    }
+}
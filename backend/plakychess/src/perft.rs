@@ -0,0 +1,127 @@
+use crate::board::Board;
+use crate::movegen::Move;
+
+impl Board {
+    pub fn perft(&mut self, depth: u32) -> u64 {
+        // Number of move sequences of the given length which can be played
+        // from the current position; comparing it against the known counts
+        // of some tricky positions is the usual way to test move generation
+        if depth == 0 {
+            return 1
+        }
+        let moves = self.legal_moves();
+        if depth == 1 {
+            return moves.len() as u64
+        }
+
+        let mut nodes = 0;
+        for mv in moves.iter() {
+            self.make_move(mv);
+            nodes += self.perft(depth - 1);
+            self.unmake_move();
+        }
+        return nodes
+    }
+
+    pub fn divide(&mut self, depth: u32) -> Vec<(Move, u64)> {
+        // Perft, split up by the first move, which narrows down where the
+        // counts go wrong when they don't match another engine's
+        let mut counts = Vec::new();
+        if depth == 0 {
+            return counts
+        }
+        for mv in self.legal_moves().iter() {
+            self.make_move(mv);
+            counts.push((*mv, self.perft(depth - 1)));
+            self.unmake_move();
+        }
+        return counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+
+    // The positions and counts are the standard ones from
+    // https://www.chessprogramming.org/Perft_Results
+
+    fn start_position() -> Board {
+        return Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap()
+    }
+
+    fn kiwipete() -> Board {
+        return Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap()
+    }
+
+    fn position_3() -> Board {
+        return Board::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap()
+    }
+
+    fn position_4() -> Board {
+        return Board::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap()
+    }
+
+    fn position_5() -> Board {
+        return Board::from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap()
+    }
+
+    fn assert_perft(mut board: Board, counts: &[u64]) {
+        for (depth, count) in counts.iter().enumerate() {
+            assert_eq!(board.perft(depth as u32 + 1), *count, "perft({})", depth + 1);
+        }
+    }
+
+    #[test]
+    fn start_position_perft() {
+        assert_perft(start_position(), &[20, 400, 8902, 197281]);
+    }
+
+    #[test]
+    fn kiwipete_perft() {
+        assert_perft(kiwipete(), &[48, 2039, 97862]);
+    }
+
+    #[test]
+    fn position_3_perft() {
+        assert_perft(position_3(), &[14, 191, 2812, 43238]);
+    }
+
+    #[test]
+    fn position_4_perft() {
+        assert_perft(position_4(), &[6, 264, 9467]);
+    }
+
+    #[test]
+    fn position_5_perft() {
+        assert_perft(position_5(), &[44, 1486, 62379]);
+    }
+
+    // These take a while, run them with `cargo test --release -- --ignored`
+    #[test]
+    #[ignore]
+    fn deep_perft() {
+        assert_eq!(start_position().perft(5), 4865609);
+        assert_eq!(kiwipete().perft(4), 4085603);
+        assert_eq!(position_3().perft(5), 674624);
+        assert_eq!(position_4().perft(4), 422333);
+        assert_eq!(position_5().perft(4), 2103487);
+    }
+
+    #[test]
+    fn divide_adds_up_to_perft() {
+        let mut board = kiwipete();
+        let counts = board.divide(3);
+        assert_eq!(counts.len(), 48);
+        assert_eq!(counts.iter().map(|(_, count)| count).sum::<u64>(), board.perft(3));
+    }
+
+    #[test]
+    fn perft_leaves_the_board_unchanged() {
+        let mut board = kiwipete();
+        let key = board.position_key();
+        board.perft(3);
+        assert_eq!(board.position_key(), key);
+        assert!(board.state == kiwipete().state);
+    }
+}
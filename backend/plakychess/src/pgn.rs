@@ -0,0 +1,544 @@
+use crate::board::{Board, GameStatus, PlayerName};
+use crate::movegen::Move;
+
+const START_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+// The tags every PGN game has, in the order they're written in
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+// Suffix annotations, and the numeric annotation glyphs they stand for
+const SUFFIX_ANNOTATIONS: [(&str, u8); 6] = [("!", 1), ("?", 2), ("!!", 3), ("??", 4), ("!?", 5), ("?!", 6)];
+
+// A game record, as read from or written to Portable Game Notation
+#[derive(Clone)]
+pub struct Game {
+    // Tags in the order they should be written in
+    pub tags: Vec<(String, String)>,
+    // The main line of the game, starting from the position in the FEN tag,
+    // or the usual starting position if there's none
+    pub moves: Vec<GameMove>,
+    // "1-0", "0-1", "1/2-1/2" or "*" while the game is still going on
+    pub result: String
+}
+
+#[derive(Clone)]
+pub struct GameMove {
+    pub mv: Move,
+    // Comments written right before and right after the move
+    pub comment_before: Option<String>,
+    pub comment: Option<String>,
+    // Numeric annotation glyphs, like 1 for a good move or 2 for a mistake
+    pub nags: Vec<u8>,
+    // Other moves which could have been played instead of this one, each
+    // followed by the rest of its line
+    pub variations: Vec<Vec<GameMove>>
+}
+
+impl GameMove {
+    pub fn new(mv: Move) -> Self {
+        return GameMove { mv, comment_before: None, comment: None, nags: Vec::new(), variations: Vec::new() }
+    }
+}
+
+impl Game {
+    pub fn new() -> Self {
+        // A game with the seven tags every game needs, all still unknown
+        let tags = SEVEN_TAG_ROSTER.iter().map(|name| {
+            let value = if *name == "Result" { "*" } else { "?" };
+            (name.to_string(), value.to_string())
+        }).collect();
+        return Game { tags, moves: Vec::new(), result: "*".to_string() }
+    }
+
+    pub fn from_moves(start: &Board, moves: &[Move]) -> Self {
+        // Records moves played on the board, like the ones `Board` keeps in
+        // its history
+        let mut game = Game::new();
+        if start.to_fen() != START_POSITION {
+            game.set_tag("SetUp", "1");
+            game.set_tag("FEN", &start.to_fen());
+        }
+        game.moves = moves.iter().map(|mv| GameMove::new(*mv)).collect();
+
+        // The result is only known if the moves end the game
+        game.result = match game.board().status() {
+            GameStatus::ONGOING => "*",
+            GameStatus::CHECKMATE(PlayerName::WHITE) => "1-0",
+            GameStatus::CHECKMATE(_) => "0-1",
+            GameStatus::STALEMATE | GameStatus::DRAW(_) => "1/2-1/2"
+        }.to_string();
+        return game
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        return self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string()))
+        }
+    }
+
+    pub fn start_position(&self) -> Board {
+        // Games read from PGN have had their FEN tag checked already, so only
+        // a tag set to an invalid FEN by hand can't be read here
+        return Board::from_fen(self.tag("FEN").unwrap_or(START_POSITION)).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn board(&self) -> Board {
+        // The position at the end of the main line, with its moves in the
+        // board's history
+        let mut board = self.start_position();
+        for game_move in self.moves.iter() {
+            board.make_move(&game_move.mv);
+        }
+        return board
+    }
+
+    pub fn from_pgn(pgn: &str) -> Result<Self, String> {
+        // Reads the first game in the text
+        return Game::read_all(pgn)?.into_iter().next().ok_or_else(|| "No game found".to_string())
+    }
+
+    pub fn read_all(pgn: &str) -> Result<Vec<Self>, String> {
+        // Reads all the games in the text, like a whole PGN collection
+        let mut parser = Parser { tokens: tokenize(pgn)?, position: 0 };
+        let mut games = Vec::new();
+        while parser.peek().is_some() {
+            games.push(parser.game()?);
+        }
+        return Ok(games)
+    }
+
+    pub fn to_pgn(&self) -> String {
+        // The seven tag roster comes first, and the other tags after it, in
+        // the order they were added in
+        let mut pgn = String::new();
+        let mut tags: Vec<(String, String)> = SEVEN_TAG_ROSTER.iter()
+            .map(|name| (name.to_string(), self.tag(name).unwrap_or("?").to_string()))
+            .collect();
+        tags[6].1 = self.result.clone();
+        tags.extend(self.tags.iter().filter(|(name, _)| !SEVEN_TAG_ROSTER.contains(&name.as_str())).cloned());
+        for (name, value) in tags.iter() {
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('"', "\\\"")));
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        write_line(&mut self.start_position(), &self.moves, &mut tokens);
+        tokens.push(self.result.clone());
+
+        // Lines of movetext shouldn't be longer than 80 characters
+        let mut line = String::new();
+        for token in tokens.iter() {
+            if !line.is_empty() && line.len() + 1 + token.len() > 79 {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() && !line.ends_with('(') && token != ")" {
+                line.push(' ');
+            }
+            line.push_str(token);
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+        return pgn
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        return Game::new()
+    }
+}
+
+fn write_line(board: &mut Board, moves: &[GameMove], tokens: &mut Vec<String>) {
+    // Writes the moves played one after another from the board, which is
+    // left the way it was. Black's moves only get a number of their own
+    // when something came in between them and white's move
+    let mut needs_number = true;
+    for game_move in moves.iter() {
+        if let Some(comment) = &game_move.comment_before {
+            tokens.push(format!("{{{}}}", comment));
+            needs_number = true;
+        }
+        let number = board.state.fullmove_number;
+        if board.state.side_to_move == PlayerName::WHITE {
+            tokens.push(format!("{}.", number));
+        } else if needs_number {
+            tokens.push(format!("{}...", number));
+        }
+        tokens.push(game_move.mv.to_san(board));
+        needs_number = false;
+
+        for nag in game_move.nags.iter() {
+            tokens.push(format!("${}", nag));
+        }
+        if let Some(comment) = &game_move.comment {
+            tokens.push(format!("{{{}}}", comment));
+            needs_number = true;
+        }
+        for variation in game_move.variations.iter() {
+            tokens.push("(".to_string());
+            write_line(board, variation, tokens);
+            tokens.push(")".to_string());
+            needs_number = true;
+        }
+
+        board.make_move(&game_move.mv);
+    }
+    for _ in moves.iter() {
+        board.unmake_move();
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    Move(String),
+    Result(String),
+    OpenVariation,
+    CloseVariation
+}
+
+fn tokenize(pgn: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = pgn.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let mut line_start = true;
+    while i < chars.len() {
+        let c = chars[i];
+        let at_line_start = line_start;
+        line_start = c == '\n';
+        match c {
+            _ if c.is_whitespace() => i += 1,
+            // Lines starting with a percent sign are left to other programs
+            '%' if at_line_start => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            },
+            ';' => {
+                let start = i + 1;
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                tokens.push(Token::Comment(chars[start..i].iter().collect::<String>().trim().to_string()));
+            },
+            '{' => {
+                let start = i + 1;
+                while i < chars.len() && chars[i] != '}' {
+                    i += 1;
+                }
+                if i == chars.len() {
+                    return Err("Unterminated comment".to_string())
+                }
+                tokens.push(Token::Comment(chars[start..i].iter().collect::<String>().trim().to_string()));
+                i += 1;
+            },
+            '[' => {
+                // [Name "Value"], where the value can contain escaped quotes
+                // and backslashes
+                i += 1;
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let name: String = chars[start..i].iter().collect();
+                while i < chars.len() && chars[i].is_whitespace() {
+                    i += 1;
+                }
+                if i == chars.len() || chars[i] != '"' {
+                    return Err(format!("Invalid tag {:?}", name))
+                }
+                i += 1;
+                let mut value = String::new();
+                while i < chars.len() && chars[i] != '"' {
+                    if chars[i] == '\\' && i + 1 < chars.len() {
+                        i += 1;
+                    }
+                    value.push(chars[i]);
+                    i += 1;
+                }
+                while i < chars.len() && chars[i] != ']' {
+                    i += 1;
+                }
+                if i == chars.len() {
+                    return Err(format!("Unterminated tag {:?}", name))
+                }
+                tokens.push(Token::Tag(name, value));
+                i += 1;
+            },
+            '(' => {
+                tokens.push(Token::OpenVariation);
+                i += 1;
+            },
+            ')' => {
+                tokens.push(Token::CloseVariation);
+                i += 1;
+            },
+            '$' => {
+                let start = i + 1;
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let nag: String = chars[start..i].iter().collect();
+                tokens.push(Token::Nag(nag.parse().map_err(|_| format!("Invalid NAG ${}", nag))?));
+            },
+            _ => {
+                let start = i;
+                while i < chars.len() && !chars[i].is_whitespace() && !"{}()[];$".contains(chars[i]) {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                tokens.extend(word_tokens(&word));
+            }
+        }
+    }
+    return Ok(tokens)
+}
+
+fn word_tokens(word: &str) -> Vec<Token> {
+    // A word of movetext can be a result, a move number, a move, or a move
+    // number and a move stuck together, like "12.e4", and moves can have
+    // suffix annotations
+    if ["1-0", "0-1", "1/2-1/2", "*"].contains(&word) {
+        return vec![Token::Result(word.to_string())]
+    }
+    // Only digits followed by dots make a move number, since castling may
+    // be written with zeros, as in "0-0"
+    let (number, rest) = word.split_at(word.len() - word.trim_start_matches(|c: char| c.is_ascii_digit()).len());
+    let after_dots = rest.trim_start_matches('.');
+    let word = if !number.is_empty() && (rest.is_empty() || after_dots.len() < rest.len()) { after_dots } else { word };
+    if word.is_empty() {
+        return Vec::new()
+    }
+
+    let san = word.trim_end_matches(['!', '?']);
+    let suffix = &word[san.len()..];
+    let mut tokens = vec![Token::Move(san.to_string())];
+    if let Some((_, nag)) = SUFFIX_ANNOTATIONS.iter().find(|(annotation, _)| *annotation == suffix) {
+        tokens.push(Token::Nag(*nag));
+    }
+    return tokens
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        return self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        return token
+    }
+
+    fn game(&mut self) -> Result<Game, String> {
+        let mut game = Game { tags: Vec::new(), moves: Vec::new(), result: "*".to_string() };
+        while let Some(Token::Tag(name, value)) = self.peek().cloned() {
+            game.set_tag(&name, &value);
+            self.next();
+        }
+        if let Some(result) = game.tag("Result") {
+            game.result = result.to_string();
+        }
+
+        let board = Board::from_fen(game.tag("FEN").unwrap_or(START_POSITION))?;
+        game.moves = self.line(board)?;
+        match self.next() {
+            Some(Token::Result(result)) => game.result = result,
+            Some(Token::CloseVariation) => return Err("Unexpected end of variation".to_string()),
+            // A game without a result ends where the next one's tags start
+            Some(Token::Tag(_, _)) => self.position -= 1,
+            _ => ()
+        }
+        return Ok(game)
+    }
+
+    fn line(&mut self, mut board: Board) -> Result<Vec<GameMove>, String> {
+        // Reads moves until the end of the variation or the game, starting
+        // from the board, and including any variations along the way
+        let mut moves: Vec<GameMove> = Vec::new();
+        let mut comment_before: Option<String> = None;
+        loop {
+            match self.peek().cloned() {
+                None | Some(Token::Result(_)) | Some(Token::Tag(_, _)) | Some(Token::CloseVariation) => break,
+                Some(Token::Comment(comment)) => {
+                    // Comments belong to the move before them, unless there
+                    // isn't one yet
+                    match moves.last_mut() {
+                        Some(last) => last.comment = Some(join_comments(last.comment.take(), comment)),
+                        None => comment_before = Some(join_comments(comment_before.take(), comment))
+                    }
+                },
+                Some(Token::Nag(nag)) => {
+                    match moves.last_mut() {
+                        Some(last) => last.nags.push(nag),
+                        None => return Err(format!("NAG ${} before any move", nag))
+                    }
+                },
+                Some(Token::OpenVariation) => {
+                    // A variation replaces the move before it, so it's played
+                    // from the position before that move
+                    self.next();
+                    if moves.is_empty() {
+                        return Err("Variation before any move".to_string())
+                    }
+                    board.unmake_move();
+                    let variation = self.line(board.clone())?;
+                    let last = moves.last_mut().unwrap();
+                    board.make_move(&last.mv);
+                    last.variations.push(variation);
+                    if self.peek() != Some(&Token::CloseVariation) {
+                        return Err("Unterminated variation".to_string())
+                    }
+                },
+                Some(Token::Move(san)) => {
+                    let mv = board.parse_san(&san)
+                        .ok_or_else(|| format!("Illegal move {:?} in position {}", san, board.to_fen()))?;
+                    board.make_move(&mv);
+                    let mut game_move = GameMove::new(mv);
+                    game_move.comment_before = comment_before.take();
+                    moves.push(game_move);
+                }
+            }
+            self.next();
+        }
+        return Ok(moves)
+    }
+}
+
+fn join_comments(first: Option<String>, second: String) -> String {
+    match first {
+        Some(first) => format!("{} {}", first, second),
+        None => second
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Game, GameMove};
+    use crate::board::{Board, GameStatus, PlayerName};
+
+    const IMMORTAL_GAME: &str = r#"[Event "London"]
+[Site "London ENG"]
+[Date "1851.06.21"]
+[Round "?"]
+[White "Adolf Anderssen"]
+[Black "Lionel Kieseritzky"]
+[Result "1-0"]
+
+1.e4 e5 2.f4 exf4 3.Bc4 Qh4+ 4.Kf1 b5 5.Bxb5 Nf6 6.Nf3 Qh6 7.d3 Nh5 8.Nh4 Qg5
+9.Nf5 c6 10.g4 Nf6 11.Rg1 cxb5 12.h4 Qg6 13.h5 Qg5 14.Qf3 Ng8 15.Bxf4 Qf6
+16.Nc3 Bc5 17.Nd5 Qxb2 18.Bd6 Bxg1 19.e5 Qxa1+ 20.Ke2 Na6 21.Nxg7+ Kd8
+22.Qf6+ Nxf6 23.Be7# 1-0
+"#;
+
+    #[test]
+    fn read_game() {
+        let game = Game::from_pgn(IMMORTAL_GAME).unwrap();
+        assert_eq!(game.tag("White"), Some("Adolf Anderssen"));
+        assert_eq!(game.result, "1-0");
+        assert_eq!(game.moves.len(), 45);
+        assert_eq!(game.board().status(), GameStatus::CHECKMATE(PlayerName::WHITE));
+    }
+
+    #[test]
+    fn write_game() {
+        let game = Game::from_pgn(IMMORTAL_GAME).unwrap();
+        let pgn = game.to_pgn();
+        assert!(pgn.starts_with("[Event \"London\"]\n[Site \"London ENG\"]\n"));
+        assert!(pgn.contains("\n\n1. e4 e5 2. f4 exf4 3. Bc4 Qh4+ 4. Kf1 b5"));
+        assert!(pgn.contains("23. Be7# 1-0\n"));
+        assert!(pgn.lines().all(|line| line.len() <= 80));
+
+        let again = Game::from_pgn(&pgn).unwrap();
+        assert_eq!(again.to_pgn(), pgn);
+    }
+
+    #[test]
+    fn annotations_and_variations() {
+        let pgn = r#"[Event "Test"]
+[FEN "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"]
+
+{Starts after 1. e4} 1... c5!? $14 (1... e5 2. Nf3 (2. f4) Nc6; the main line
+) 2. Nf3 d6?! { Not the best } 3. d4 *"#;
+        let game = Game::from_pgn(pgn).unwrap();
+        assert_eq!(game.moves.len(), 4);
+        assert_eq!(game.result, "*");
+
+        let first = &game.moves[0];
+        assert_eq!(first.comment_before.as_deref(), Some("Starts after 1. e4"));
+        assert_eq!(first.nags, vec![5, 14]);
+        assert_eq!(first.variations.len(), 1);
+        let variation = &first.variations[0];
+        assert_eq!(variation.len(), 3);
+        assert_eq!(variation[1].variations.len(), 1);
+        assert_eq!(variation[2].comment.as_deref(), Some("the main line"));
+        assert_eq!(game.moves[2].nags, vec![6]);
+        assert_eq!(game.moves[2].comment.as_deref(), Some("Not the best"));
+
+        let written = game.to_pgn();
+        assert!(written.replace('\n', " ").contains("{Starts after 1. e4} 1... c5 $5 $14 (1... e5 2. Nf3 (2. f4) 2... Nc6 {the main line}) 2. Nf3 d6 $6 {Not the best} 3. d4 *"));
+        assert_eq!(Game::from_pgn(&written).unwrap().to_pgn(), written);
+    }
+
+    #[test]
+    fn collection() {
+        let pgn = "[Event \"1\"]\n\n1. e4 e5 1/2-1/2\n\n[Event \"2\"]\n\n1. d4 *\n[Event \"3\"]\n1. c4";
+        let games = Game::read_all(pgn).unwrap();
+        assert_eq!(games.len(), 3);
+        assert_eq!(games[0].result, "1/2-1/2");
+        assert_eq!(games[1].moves.len(), 1);
+        assert_eq!(games[2].tag("Event"), Some("3"));
+    }
+
+    #[test]
+    fn from_moves() {
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let start = board.clone();
+        let castle = board.parse_san("O-O").unwrap();
+        board.make_move(&castle);
+        let reply = board.parse_san("Kd7").unwrap();
+        let mut game = Game::from_moves(&start, &[castle, reply]);
+        game.moves[1] = GameMove { comment: Some("forced".to_string()), ..game.moves[1].clone() };
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/4K2R w K - 0 1\"]\n"));
+        assert!(pgn.ends_with("1. O-O Kd7 {forced} *\n"));
+
+        let mate = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let game = Game::from_moves(&mate, &[mate.parse_san("Ra8").unwrap()]);
+        assert!(game.to_pgn().contains("[Result \"1-0\"]"));
+        assert!(game.to_pgn().ends_with("1. Ra8# 1-0\n"));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(Game::from_pgn("1. e4 e4").is_err());
+        assert!(Game::from_pgn("1. e4 {unterminated").is_err());
+        assert!(Game::from_pgn("1. e4 (1. d4").is_err());
+        assert!(Game::from_pgn("").is_err());
+        assert!(Game::from_pgn("[FEN \"8/8/8/8/8/8/8/8 w - - 0 1\"]\n\n*").is_err());
+        assert!(Game::from_pgn("[SetUp \"1\"]\n[FEN \"not a fen\"]\n\n1. e4 *").is_err());
+    }
+
+    #[test]
+    fn castling_with_zeros() {
+        let game = Game::from_pgn("1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. 0-0 Nf6 5.d3 0-0 *").unwrap();
+        assert_eq!(game.moves.len(), 10);
+        assert!(game.moves[6].mv.is_castling() && game.moves[9].mv.is_castling());
+        assert!(game.to_pgn().contains("4. O-O Nf6 5. d3 O-O *"));
+    }
+}
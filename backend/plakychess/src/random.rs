@@ -0,0 +1,22 @@
+// A small xorshift generator, good enough for picking moves and magic
+// numbers, which doesn't need any crates
+pub struct Xorshift(u64);
+
+impl Xorshift {
+    pub fn new(seed: u64) -> Self {
+        // The generator would only ever return zeros if it started from zero
+        return Xorshift(if seed == 0 { 0x9e3779b97f4a7c15 } else { seed })
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        return self.0
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        // A number from 0 up to, but not including, n
+        return (self.next() % n as u64) as usize
+    }
+}
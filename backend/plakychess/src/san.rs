@@ -0,0 +1,216 @@
+use crate::bitboard::{ROWS, parse_square, square_name};
+use crate::board::{Board, SquareContent};
+use crate::movegen::Move;
+
+impl Move {
+    pub fn to_san(&self, board: &Board) -> String {
+        // Standard Algebraic Notation of the move, which has to be played
+        // from the given position: the letter of the moving figure, whatever
+        // is needed to tell it apart from the figures of the same kind which
+        // could move to the same square, and the target square, followed by
+        // the promotion and whether the move gives check or checkmate
+        let (moving, _) = board.figure_at(self.from);
+        let mut san = String::new();
+        if self.is_castling() {
+            san.push_str(if self.to.0 > self.from.0 { "O-O" } else { "O-O-O" });
+        } else if moving == SquareContent::PAWN {
+            // Pawns are told apart by the file they capture from
+            if self.is_capture() {
+                san.push(square_name(self.from).chars().next().unwrap());
+                san.push('x');
+            }
+            san.push_str(&square_name(self.to));
+            if let Some(promotion) = self.promotion {
+                san.push('=');
+                san.push(promotion.letter());
+            }
+        } else {
+            san.push(moving.letter());
+            let rivals: Vec<Move> = board.legal_moves().into_iter()
+                .filter(|mv| mv.to == self.to && mv.from != self.from && board.figure_at(mv.from).0 == moving)
+                .collect();
+            if !rivals.is_empty() {
+                // The file is enough if it's different from all the others,
+                // otherwise the rank, and only if neither is, both
+                let from = square_name(self.from);
+                if rivals.iter().all(|mv| mv.from.0 != self.from.0) {
+                    san.push_str(&from[..1]);
+                } else if rivals.iter().all(|mv| mv.from.1 != self.from.1) {
+                    san.push_str(&from[1..]);
+                } else {
+                    san.push_str(&from);
+                }
+            }
+            if self.is_capture() {
+                san.push('x');
+            }
+            san.push_str(&square_name(self.to));
+        }
+
+        let mut after = board.clone();
+        after.make_move(self);
+        if after.is_in_check(after.state.side_to_move) {
+            san.push(if after.legal_moves().is_empty() { '#' } else { '+' });
+        }
+        return san
+    }
+}
+
+impl Board {
+    pub fn parse_san(&self, san: &str) -> Option<Move> {
+        // Finds the legal move of the side to move the SAN describes. Check
+        // and annotation suffixes are ignored, and so are a few common ways
+        // of bending the notation, like zeros when castling or a promotion
+        // without the equals sign
+        let san = san.trim().trim_end_matches(|c| "+#!?".contains(c));
+        let san = san.strip_suffix("e.p.").unwrap_or(san).trim_end();
+        let moves = self.legal_moves();
+
+        if san == "O-O" || san == "0-0" || san == "O-O-O" || san == "0-0-0" {
+            let king_side = san.len() == 3;
+            return moves.into_iter().find(|mv| mv.is_castling() && (mv.to.0 > mv.from.0) == king_side)
+        }
+
+        let mut chars: Vec<char> = san.chars().collect();
+        let moving = match chars.first() {
+            Some(letter) if letter.is_ascii_uppercase() => {
+                let moving = SquareContent::from_letter(*letter)?;
+                chars.remove(0);
+                moving
+            },
+            _ => SquareContent::PAWN
+        };
+
+        // A promotion comes after the target square, and is the only thing
+        // which can end with a letter
+        let mut promotion = None;
+        if let Some(letter) = chars.last() {
+            if letter.is_ascii_uppercase() {
+                promotion = Some(SquareContent::from_letter(*letter)?);
+                chars.pop();
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+            }
+        }
+
+        if chars.len() < 2 {
+            return None
+        }
+        let to = parse_square(&chars[chars.len() - 2..].iter().collect::<String>())?;
+        chars.truncate(chars.len() - 2);
+        if chars.last() == Some(&'x') {
+            chars.pop();
+        }
+
+        // Whatever is left tells the moving figure apart from the others
+        let mut from_x = None;
+        let mut from_y = None;
+        for c in chars.iter() {
+            match c {
+                'a'..='h' => from_x = Some(*c as usize - 'a' as usize),
+                '1'..='8' => from_y = Some(ROWS - c.to_digit(10).unwrap() as usize),
+                _ => return None
+            }
+        }
+
+        let mut candidates = moves.into_iter().filter(|mv| {
+            mv.to == to
+                && mv.promotion == promotion
+                && !mv.is_castling()
+                && self.figure_at(mv.from).0 == moving
+                && from_x.is_none_or(|x| mv.from.0 == x)
+                && from_y.is_none_or(|y| mv.from.1 == y)
+        });
+        let mv = candidates.next()?;
+        if candidates.next().is_some() {
+            // Ambiguous
+            return None
+        }
+        return Some(mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+
+    fn san_of_all_moves(fen: &str) -> Vec<String> {
+        let board = Board::from_fen(fen).unwrap();
+        let mut sans: Vec<String> = board.legal_moves().iter().map(|mv| mv.to_san(&board)).collect();
+        sans.sort();
+        return sans
+    }
+
+    #[test]
+    fn pawn_and_figure_moves() {
+        let board = Board::from_fen("rnbqkbnr/pppp1ppp/8/4p3/3P4/8/PPP1PPPP/RNBQKBNR w KQkq e6 0 2").unwrap();
+        let sans: Vec<String> = board.legal_moves().iter().map(|mv| mv.to_san(&board)).collect();
+        for san in ["dxe5", "d5", "e4", "Nf3", "Qd3", "Bg5", "Kd2"].iter() {
+            assert!(sans.contains(&san.to_string()), "{} not in {:?}", san, sans);
+        }
+    }
+
+    #[test]
+    fn disambiguation() {
+        // Knights on b1 and f1 can both reach d2, rooks on a1 and a5 both a3,
+        // and three queens can reach c3
+        let sans = san_of_all_moves("6k1/8/8/R7/1Q1Q4/8/1Q6/RN1K1N2 w - - 0 1");
+        for san in ["Nbd2", "Nfd2", "R1a3", "R5a3", "Qdc3", "Qb4c3", "Q2c3"].iter() {
+            assert!(sans.contains(&san.to_string()), "{} not in {:?}", san, sans);
+        }
+        assert!(!sans.contains(&"Nd2".to_string()));
+        assert!(sans.contains(&"Ne3".to_string()));
+    }
+
+    #[test]
+    fn castling_promotion_and_check() {
+        let board = Board::from_fen("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let sans: Vec<String> = board.legal_moves().iter().map(|mv| mv.to_san(&board)).collect();
+        for san in ["O-O", "O-O-O", "b8=Q+", "bxa8=N", "Rxa8+"].iter() {
+            assert!(sans.contains(&san.to_string()), "{} not in {:?}", san, sans);
+        }
+    }
+
+    #[test]
+    fn checkmate() {
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let mv = board.parse_san("Ra8").unwrap();
+        assert_eq!(mv.to_san(&board), "Ra8#");
+    }
+
+    #[test]
+    fn parse() {
+        let board = Board::from_fen("r3k2r/1P6/8/8/8/5N2/8/RN2K2R w KQkq - 0 1").unwrap();
+        assert_eq!(board.parse_san("Nbd2").unwrap().from, (1, 7));
+        assert_eq!(board.parse_san("Nfd2").unwrap().from, (5, 5));
+        assert!(board.parse_san("Nd2").is_none());
+        assert_eq!(board.parse_san("0-0").unwrap().to, (6, 7));
+        assert!(board.parse_san("O-O-O").is_none());
+        assert!(board.parse_san("bxa8=Q+").unwrap().is_capture());
+        assert!(board.parse_san("b8N").unwrap().promotion.is_some());
+        assert!(board.parse_san("b8").is_none());
+        assert!(board.parse_san("Ke3").is_none());
+        assert!(board.parse_san("Zz9").is_none());
+    }
+
+    #[test]
+    fn en_passant() {
+        // Both pawns on c5 and e5 can take the pawn on d5 en passant
+        let board = Board::from_fen("4k3/8/8/2PpP3/8/8/8/4K3 w - d6 0 2").unwrap();
+        let sans = san_of_all_moves("4k3/8/8/2PpP3/8/8/8/4K3 w - d6 0 2");
+        assert!(sans.contains(&"cxd6".to_string()));
+        assert!(sans.contains(&"exd6".to_string()));
+        assert!(board.parse_san("exd6 e.p.").unwrap().is_en_passant());
+        assert_eq!(board.parse_san("cxd6").unwrap().from, (2, 3));
+        assert!(board.parse_san("xd6").is_none());
+    }
+
+    #[test]
+    fn round_trip() {
+        let board = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        for mv in board.legal_moves().iter() {
+            assert_eq!(board.parse_san(&mv.to_san(&board)), Some(*mv));
+        }
+    }
+}
@@ -0,0 +1,497 @@
+use std::time::{Duration, Instant};
+
+use crate::board::{Board, SquareContent};
+use crate::engine::Strategy;
+use crate::eval::evaluate;
+use crate::movegen::Move;
+use crate::transposition::{Bound, Entry, TranspositionTable, shared_table};
+
+// Score of being checkmated right away; getting mated later is a bit less
+// bad, so the search prefers the quickest mates and the slowest losses
+pub const MATE_SCORE: i32 = 100_000;
+
+// Scores this close to the mate score can only come from a mate
+const MATE_THRESHOLD: i32 = MATE_SCORE - 1000;
+
+pub struct SearchResult {
+    // None only when the side to move has no legal moves
+    pub best_move: Option<Move>,
+    // In centipawns, from the point of view of the side to move
+    pub score: i32,
+    // The moves both sides are expected to play, starting with the best move
+    pub principal_variation: Vec<Move>,
+    pub depth: u32,
+    pub nodes: u64
+}
+
+impl SearchResult {
+    pub fn mate_in(&self) -> Option<i32> {
+        // Number of the side to move's own moves until it mates, or minus the
+        // number of moves until it gets mated, if the score says either
+        if self.score.abs() < MATE_THRESHOLD {
+            return None
+        }
+        let plies = MATE_SCORE - self.score.abs();
+        let moves = (plies + 1) / 2;
+        return Some(if self.score > 0 { moves } else { -moves })
+    }
+}
+
+// How far the search may go; it stops at whichever limit it reaches first
+#[derive(Copy, Clone, Debug)]
+pub struct SearchLimits {
+    pub depth: u32,
+    pub time: Option<Duration>,
+    pub nodes: Option<u64>
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u32) -> Self {
+        return SearchLimits { depth, time: None, nodes: None }
+    }
+}
+
+// The deepest the search ever goes, when only time or nodes limit it
+pub const MAX_DEPTH: u32 = 64;
+
+// How many nodes are searched between two looks at the clock
+const NODES_BETWEEN_TIME_CHECKS: u64 = 1024;
+
+// Negamax search with alpha-beta pruning: the score of a position is the
+// best of the negated scores of the positions its moves lead to, and lines
+// which can't affect the final choice, because one of the sides has a better
+// alternative earlier on, aren't searched any further.
+//
+// The search is run again and again, one ply deeper every time, until one
+// of the limits is reached. An iteration cut short by the limits is thrown
+// away, and the result of the last one completed is used; the first one is
+// always completed, so there's always a move to play. Searching the best
+// line of the previous iteration first makes the deeper iterations faster.
+//
+// Positions already searched deep enough, possibly in an earlier search,
+// aren't searched again as long as their score in the transposition table
+// settles the search, and otherwise the best move found for them is tried
+// first
+pub struct Search<'a> {
+    limits: SearchLimits,
+    table: &'a mut TranspositionTable,
+    start: Instant,
+    nodes: u64,
+    stopped: bool,
+    previous_variation: Vec<Move>
+}
+
+impl<'a> Search<'a> {
+    pub fn new(limits: SearchLimits, table: &'a mut TranspositionTable) -> Self {
+        return Search { limits, table, start: Instant::now(), nodes: 0, stopped: false, previous_variation: Vec::new() }
+    }
+
+    pub fn run(&mut self, board: &mut Board) -> SearchResult {
+        self.start = Instant::now();
+        self.nodes = 0;
+        self.stopped = false;
+        self.previous_variation.clear();
+
+        let mut result = SearchResult { best_move: None, score: 0, principal_variation: Vec::new(), depth: 0, nodes: 0 };
+        for depth in 1..=self.limits.depth.max(1) {
+            let mut principal_variation = Vec::new();
+            let score = self.negamax(board, depth, 0, -MATE_SCORE, MATE_SCORE, &mut principal_variation);
+            if self.stopped {
+                break;
+            }
+            result = SearchResult {
+                best_move: principal_variation.first().copied(),
+                score,
+                principal_variation: principal_variation.clone(),
+                depth,
+                nodes: self.nodes
+            };
+            self.previous_variation = principal_variation;
+
+            // Nothing changes once the game is over, or a mate is found which
+            // the search saw all the way to the end of
+            if result.best_move.is_none() || result.mate_in().is_some_and(|moves| moves.abs() * 2 <= depth as i32) {
+                break;
+            }
+        }
+        result.nodes = self.nodes;
+        return result
+    }
+
+    fn out_of_budget(&mut self) -> bool {
+        // The limits only apply once the first iteration is done
+        if self.stopped || self.previous_variation.is_empty() {
+            return self.stopped
+        }
+        if self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes) {
+            self.stopped = true;
+        }
+        if let Some(time) = self.limits.time {
+            if self.nodes.is_multiple_of(NODES_BETWEEN_TIME_CHECKS) && self.start.elapsed() >= time {
+                self.stopped = true;
+            }
+        }
+        return self.stopped
+    }
+
+    fn negamax(&mut self, board: &mut Board, depth: u32, ply: i32, mut alpha: i32, beta: i32, principal_variation: &mut Vec<Move>) -> i32 {
+        // The score of the position, as long as it's between alpha and beta;
+        // otherwise only a bound on it. The principal variation is filled in
+        // with the moves leading to the score
+        self.nodes += 1;
+        principal_variation.clear();
+        if self.out_of_budget() {
+            return 0
+        }
+
+        // Repeating a position, even once, is scored as a draw, since the side
+        // which benefited from it could just repeat it again
+        if ply > 0 && (board.state.halfmove_clock >= 100 || board.repetitions() > 1 || board.has_insufficient_material()) {
+            return 0
+        }
+
+        // The root is always searched, so that there's a move to play. So is
+        // a position whose exact score falls between alpha and beta, since
+        // that score and the line leading to it become part of the principal
+        // variation, which the table doesn't keep
+        let key = board.position_key();
+        let entry = self.table.probe(key);
+        if let Some(entry) = entry {
+            let score = score_from_table(entry.score, ply);
+            let settled = match entry.bound {
+                Bound::EXACT => score <= alpha || score >= beta,
+                Bound::LOWER => score >= beta,
+                Bound::UPPER => score <= alpha
+            };
+            if ply > 0 && entry.depth >= depth && settled {
+                return score
+            }
+        }
+
+        let mut moves = board.legal_moves();
+        if moves.is_empty() {
+            if board.is_in_check(board.state.side_to_move) {
+                return -MATE_SCORE + ply
+            }
+            return 0
+        }
+        if depth == 0 {
+            return self.quiescence(board, ply, alpha, beta)
+        }
+
+        let best_move = entry.and_then(|entry| entry.best_move).or_else(|| self.previous_variation.get(ply as usize).copied());
+        order_moves(board, &mut moves, best_move);
+        let original_alpha = alpha;
+        let mut line = Vec::new();
+        for mv in moves.iter() {
+            board.make_move(mv);
+            let score = -self.negamax(board, depth - 1, ply + 1, -beta, -alpha, &mut line);
+            board.unmake_move();
+            if self.stopped {
+                return 0
+            }
+
+            if score > alpha {
+                alpha = score;
+                principal_variation.clear();
+                principal_variation.push(*mv);
+                principal_variation.extend_from_slice(&line);
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+
+        // When no move beats alpha, the position is worse than some other
+        // line, and no move is best in it; any one will do then, so that the
+        // root always has a move to play
+        let bound = if alpha >= beta {
+            Bound::LOWER
+        } else if alpha > original_alpha {
+            Bound::EXACT
+        } else {
+            Bound::UPPER
+        };
+        let best_move = principal_variation.first().copied().or(best_move);
+        self.table.store(Entry { key, depth, bound, score: score_to_table(alpha, ply), best_move });
+
+        if principal_variation.is_empty() && ply == 0 {
+            principal_variation.push(moves[0]);
+        }
+        return alpha
+    }
+
+    fn quiescence(&mut self, board: &mut Board, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+        // The evaluation can't be trusted while figures are still being
+        // captured, since it doesn't see that a figure which just took
+        // another one is about to be taken back. So captures and promotions
+        // are played out until the position is quiet, with the side to move
+        // free to stop capturing whenever the evaluation, the "stand pat"
+        // score, is already better than what the captures lead to. In check
+        // there's no standing pat, and every way out of it is tried instead
+        self.nodes += 1;
+        if self.out_of_budget() {
+            return 0
+        }
+        if board.has_insufficient_material() {
+            return 0
+        }
+
+        let in_check = board.is_in_check(board.state.side_to_move);
+        let mut moves = board.legal_moves();
+        if in_check {
+            if moves.is_empty() {
+                return -MATE_SCORE + ply
+            }
+        } else {
+            let stand_pat = evaluate(board);
+            if stand_pat >= beta {
+                return stand_pat
+            }
+            alpha = alpha.max(stand_pat);
+            moves.retain(|mv| mv.is_capture() || mv.is_en_passant() || mv.promotion.is_some());
+        }
+
+        order_moves(board, &mut moves, None);
+        for mv in moves.iter() {
+            board.make_move(mv);
+            let score = -self.quiescence(board, ply + 1, -beta, -alpha);
+            board.unmake_move();
+            if self.stopped {
+                return 0
+            }
+
+            if score > alpha {
+                alpha = score;
+                if alpha >= beta {
+                    break;
+                }
+            }
+        }
+        return alpha
+    }
+}
+
+fn score_to_table(score: i32, ply: i32) -> i32 {
+    // Mate scores count the plies from the root, but the same position may
+    // come up at a different ply, so the table counts them from the position
+    if score >= MATE_THRESHOLD {
+        return score + ply
+    }
+    if score <= -MATE_THRESHOLD {
+        return score - ply
+    }
+    return score
+}
+
+fn score_from_table(score: i32, ply: i32) -> i32 {
+    if score >= MATE_THRESHOLD {
+        return score - ply
+    }
+    if score <= -MATE_THRESHOLD {
+        return score + ply
+    }
+    return score
+}
+
+fn order_moves(board: &Board, moves: &mut [Move], best_move: Option<Move>) {
+    // Alpha-beta cuts off more of the search when the best moves are tried
+    // first. Those are usually the move which turned out best before, then
+    // captures of the most valuable figures with the least valuable ones, and
+    // promotions
+    moves.sort_by_cached_key(|mv| {
+        if Some(*mv) == best_move {
+            return i32::MIN
+        }
+        let mut score = 0;
+        if mv.is_en_passant() {
+            score += 10 * SquareContent::PAWN.value();
+        } else if mv.is_capture() {
+            score += 10 * board.figure_at(mv.to).0.value() - board.figure_at(mv.from).0.value();
+        }
+        if let Some(promotion) = mv.promotion {
+            score += promotion.value();
+        }
+        return -score
+    });
+}
+
+// Plays the best move a search within the limits finds
+pub struct AlphaBetaStrategy {
+    pub limits: SearchLimits
+}
+
+impl Strategy for AlphaBetaStrategy {
+    fn choose_move(&mut self, board: &mut Board) -> Option<Move> {
+        return Search::new(self.limits, &mut shared_table()).run(board).best_move
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{MATE_SCORE, MAX_DEPTH, Search, SearchLimits};
+    use crate::board::Board;
+    use crate::eval::evaluate;
+    use crate::transposition::TranspositionTable;
+
+    fn search(fen: &str, depth: u32) -> super::SearchResult {
+        let mut board = Board::from_fen(fen).unwrap();
+        let result = Search::new(SearchLimits::depth(depth), &mut TranspositionTable::new(1).unwrap()).run(&mut board);
+        assert_eq!(board.to_fen(), Board::from_fen(fen).unwrap().to_fen());
+        return result
+    }
+
+    #[test]
+    fn mate_in_one() {
+        let result = search("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 2);
+        assert_eq!(result.best_move.unwrap().to_uci(), "a1a8");
+        assert_eq!(result.score, MATE_SCORE - 1);
+        assert_eq!(result.mate_in(), Some(1));
+    }
+
+    #[test]
+    fn mate_in_two() {
+        // 1. Kg6 Kg8 2. Ra8#, or 1. Kf7 Kh7 2. Rh1#
+        let result = search("7k/8/5K2/8/8/8/8/R7 w - - 0 1", 4);
+        assert_eq!(result.mate_in(), Some(2));
+        assert_eq!(result.principal_variation.len(), 3);
+    }
+
+    #[test]
+    fn getting_mated() {
+        let result = search("6k1/5ppp/8/8/8/8/r7/7K w - - 0 1", 3);
+        assert!(result.score < 0);
+        let result = search("6k1/5ppp/8/8/8/8/r7/r6K w - - 0 1", 1);
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.score, -MATE_SCORE);
+    }
+
+    #[test]
+    fn wins_material() {
+        // The knight forks the king and the queen
+        let result = search("4k3/8/8/1q6/4N3/8/7P/4K3 w - - 0 1", 3);
+        assert_eq!(result.best_move.unwrap().to_uci(), "e4d6");
+        assert!(result.score > 0);
+    }
+
+    #[test]
+    fn stalemate_is_a_draw() {
+        let result = search("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 2);
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.score, 0);
+    }
+
+    #[test]
+    fn principal_variation_is_legal() {
+        let mut board = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let result = Search::new(SearchLimits::depth(3), &mut TranspositionTable::new(1).unwrap()).run(&mut board);
+        assert_eq!(result.principal_variation.len(), 3);
+        assert_eq!(result.best_move, result.principal_variation.first().copied());
+        for mv in result.principal_variation.iter() {
+            assert!(board.legal_moves().contains(mv));
+            board.make_move(mv);
+        }
+    }
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    #[test]
+    fn time_limit() {
+        let mut board = Board::from_fen(KIWIPETE).unwrap();
+        let start = Instant::now();
+        let limits = SearchLimits { depth: MAX_DEPTH, time: Some(Duration::from_millis(200)), nodes: None };
+        let result = Search::new(limits, &mut TranspositionTable::new(1).unwrap()).run(&mut board);
+        assert!(start.elapsed() < Duration::from_millis(1000));
+        assert!(result.depth >= 1 && result.depth < MAX_DEPTH);
+        assert!(board.legal_moves().contains(&result.best_move.unwrap()));
+        assert_eq!(board.to_fen(), KIWIPETE);
+    }
+
+    #[test]
+    fn node_limit() {
+        let mut board = Board::from_fen(KIWIPETE).unwrap();
+        let limits = SearchLimits { depth: MAX_DEPTH, time: None, nodes: Some(5000) };
+        let result = Search::new(limits, &mut TranspositionTable::new(1).unwrap()).run(&mut board);
+        assert!(result.depth >= 1);
+        assert_eq!(result.principal_variation.len() as u32, result.depth);
+
+        // The first iteration always finishes, however tight the budget
+        let limits = SearchLimits { depth: MAX_DEPTH, time: Some(Duration::from_millis(0)), nodes: Some(1) };
+        let result = Search::new(limits, &mut TranspositionTable::new(1).unwrap()).run(&mut board);
+        assert_eq!(result.depth, 1);
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn deepening_matches_fixed_depth() {
+        // The best line of the last iteration is tried first, and the table
+        // is filled in by the earlier iterations, which can only change how
+        // fast the same score is found
+        for depth in 1..4 {
+            let mut board = Board::from_fen(KIWIPETE).unwrap();
+            let mut table = TranspositionTable::new(1).unwrap();
+            let deepened = Search::new(SearchLimits::depth(depth), &mut table).run(&mut board);
+            table.clear();
+            let mut search = Search::new(SearchLimits::depth(depth), &mut table);
+            let direct = search.negamax(&mut board, depth, 0, -MATE_SCORE, MATE_SCORE, &mut Vec::new());
+            assert_eq!(deepened.score, direct);
+        }
+    }
+
+    #[test]
+    fn table_is_reused() {
+        // A search of a position already searched just looks its score up
+        let mut board = Board::from_fen(KIWIPETE).unwrap();
+        let mut table = TranspositionTable::new(1).unwrap();
+        let first = Search::new(SearchLimits::depth(4), &mut table).run(&mut board);
+        let second = Search::new(SearchLimits::depth(4), &mut table).run(&mut board);
+        assert_eq!(first.score, second.score);
+        assert_eq!(first.best_move, second.best_move);
+        assert!(second.nodes * 10 < first.nodes);
+        assert_eq!(second.principal_variation.len(), 4);
+    }
+
+    #[test]
+    fn mate_scores_in_the_table() {
+        // The mate is found at different plies through different move orders,
+        // and has to be scored by its distance from the root every time
+        let mut board = Board::from_fen("7k/8/5K2/8/8/8/8/R7 w - - 0 1").unwrap();
+        let mut table = TranspositionTable::new(1).unwrap();
+        for depth in 3..6 {
+            let result = Search::new(SearchLimits::depth(depth), &mut table).run(&mut board);
+            assert_eq!(result.mate_in(), Some(2));
+        }
+    }
+
+    #[test]
+    fn defended_pawns_are_left_alone() {
+        // Taking the pawn with the queen looks like winning it, until the
+        // other pawn takes the queen back
+        let result = search("4k3/4p3/3p4/8/8/8/8/3QK3 w - - 0 1", 1);
+        assert_ne!(result.best_move.unwrap().to_uci(), "d1d6");
+        assert!(result.score > 500);
+
+        // And a hanging figure is taken, even at the last ply
+        let result = search("4k3/4p3/8/8/3n4/8/8/3QK3 w - - 0 1", 1);
+        assert_eq!(result.best_move.unwrap().to_uci(), "d1d4");
+    }
+
+    #[test]
+    fn exchanges_are_played_out() {
+        // The pawn takes the knight and gets taken back, and black is better
+        // off doing that than standing pat
+        let mut board = Board::from_fen("4k3/8/2p5/3N4/4P3/8/8/4K3 b - - 0 1").unwrap();
+        let mut table = TranspositionTable::new(1).unwrap();
+        let mut search = Search::new(SearchLimits::depth(1), &mut table);
+        let quiet = search.quiescence(&mut board, 0, -MATE_SCORE, MATE_SCORE);
+        assert!(quiet > evaluate(&board));
+
+        for uci in ["c6d5", "e4d5"].iter() {
+            let mv = board.parse_uci_move(uci).unwrap();
+            board.make_move(&mv);
+        }
+        assert_eq!(quiet, evaluate(&board));
+    }
+}
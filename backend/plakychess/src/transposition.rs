@@ -0,0 +1,153 @@
+use std::mem::size_of;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use crate::movegen::Move;
+
+// What the stored score says about the real score of the position: the
+// search only finds it exactly when it falls between alpha and beta
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Bound {
+    EXACT,
+    // The real score is at least this high; a move was good enough to cut
+    // the search off before the rest were tried
+    LOWER,
+    // The real score is at most this high; no move beat alpha
+    UPPER
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Entry {
+    pub key: u64,
+    pub depth: u32,
+    pub bound: Bound,
+    pub score: i32,
+    pub best_move: Option<Move>
+}
+
+// Results of searches of positions already seen, so that a position reached
+// again through a different order of moves doesn't have to be searched again.
+// Every position has a single slot, picked by its key, which it shares with
+// many others, so newer entries replace older ones
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>
+}
+
+pub const DEFAULT_SIZE_MB: usize = 16;
+// Far more than a search in the time the bot gets could fill, and small
+// enough that asking for it doesn't take the memory of the whole machine
+pub const MAX_SIZE_MB: usize = 4096;
+
+impl TranspositionTable {
+    pub fn new(megabytes: usize) -> Result<Self, String> {
+        let mut table = TranspositionTable { entries: Vec::new() };
+        table.resize(megabytes)?;
+        return Ok(table)
+    }
+
+    pub fn resize(&mut self, megabytes: usize) -> Result<(), String> {
+        // Everything stored so far is lost, since the slots of the positions
+        // depend on the size of the table. A size which isn't allowed leaves
+        // the table as it was
+        let bytes = megabytes.checked_mul(1024 * 1024)
+            .filter(|_| megabytes <= MAX_SIZE_MB)
+            .ok_or_else(|| format!("Invalid hash size {} MB, expected at most {} MB", megabytes, MAX_SIZE_MB))?;
+        let length = (bytes / size_of::<Option<Entry>>()).max(1);
+        self.entries = vec![None; length];
+        return Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        for entry in self.entries.iter_mut() {
+            *entry = None;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        return self.entries.len()
+    }
+
+    fn index(&self, key: u64) -> usize {
+        return (key % self.entries.len() as u64) as usize
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        // Different positions can share a slot, so the key has to match too
+        return self.entries[self.index(key)].filter(|entry| entry.key == key)
+    }
+
+    pub fn store(&mut self, entry: Entry) {
+        // A deeper search of the same position is worth more than a
+        // shallower one, but any other position replaces what was there
+        let index = self.index(entry.key);
+        if let Some(stored) = self.entries[index] {
+            if stored.key == entry.key && stored.depth > entry.depth {
+                return
+            }
+        }
+        self.entries[index] = Some(entry);
+    }
+}
+
+static SHARED_TABLE: OnceLock<Mutex<TranspositionTable>> = OnceLock::new();
+
+pub fn shared_table() -> MutexGuard<'static, TranspositionTable> {
+    // The table the bot keeps between its moves. A search which panicked
+    // while holding it can't have left it in a state worse than some
+    // entries being missing or stale
+    let table = SHARED_TABLE.get_or_init(|| Mutex::new(TranspositionTable::new(DEFAULT_SIZE_MB).unwrap()));
+    return table.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bound, Entry, MAX_SIZE_MB, TranspositionTable};
+
+    fn entry(key: u64, depth: u32) -> Entry {
+        return Entry { key, depth, bound: Bound::EXACT, score: depth as i32, best_move: None }
+    }
+
+    #[test]
+    fn store_and_probe() {
+        let mut table = TranspositionTable::new(1).unwrap();
+        let length = table.len() as u64;
+        assert!(length > 1000);
+
+        table.store(entry(5, 3));
+        assert_eq!(table.probe(5).unwrap().depth, 3);
+        // A different position in the same slot
+        assert!(table.probe(5 + length).is_none());
+
+        // Shallower searches of the same position don't replace deeper ones
+        table.store(entry(5, 2));
+        assert_eq!(table.probe(5).unwrap().depth, 3);
+        table.store(entry(5, 4));
+        assert_eq!(table.probe(5).unwrap().depth, 4);
+
+        // Other positions always do
+        table.store(entry(5 + length, 1));
+        assert!(table.probe(5).is_none());
+        assert_eq!(table.probe(5 + length).unwrap().depth, 1);
+
+        table.clear();
+        assert!(table.probe(5 + length).is_none());
+    }
+
+    #[test]
+    fn size() {
+        let small = TranspositionTable::new(1).unwrap();
+        let large = TranspositionTable::new(4).unwrap();
+        assert_eq!(large.len(), 4 * small.len());
+        assert_eq!(TranspositionTable::new(0).unwrap().len(), 1);
+
+        // Sizes too large to allocate, or even to count in bytes, are
+        // refused without touching the table
+        let mut table = TranspositionTable::new(1).unwrap();
+        table.store(entry(5, 3));
+        assert!(table.resize(MAX_SIZE_MB + 1).is_err());
+        assert!(table.resize(usize::MAX).is_err());
+        assert!(TranspositionTable::new(usize::MAX / 1024).is_err());
+        assert_eq!(table.len(), small.len());
+        assert_eq!(table.probe(5).unwrap().depth, 3);
+    }
+}
@@ -0,0 +1,94 @@
+use crate::bitboard::{parse_square, square_name};
+use crate::board::{Board, SquareContent};
+use crate::movegen::Move;
+
+impl Move {
+    pub fn to_uci(&self) -> String {
+        // Long algebraic notation as used by UCI: the square the figure moves
+        // from and the one it moves to, followed by the lowercase letter of
+        // the promotion, like "e2e4" or "e7e8q". Castling is written as the
+        // king's move
+        let mut uci = format!("{}{}", square_name(self.from), square_name(self.to));
+        if let Some(promotion) = self.promotion {
+            uci.push(promotion.letter().to_ascii_lowercase());
+        }
+        return uci
+    }
+}
+
+// The square a figure moves from, the one it moves to, and its promotion
+pub type UciMove = ((usize, usize), (usize, usize), Option<SquareContent>);
+
+pub fn parse_uci(uci: &str) -> Option<UciMove> {
+    // The squares and the promotion of a UCI move, which may or may not be
+    // legal, or even make sense
+    if !uci.is_ascii() || (uci.len() != 4 && uci.len() != 5) {
+        return None
+    }
+    let from = parse_square(&uci[0..2])?;
+    let to = parse_square(&uci[2..4])?;
+    let promotion = match uci[4..].chars().next() {
+        Some(letter) => match SquareContent::from_letter(letter)? {
+            SquareContent::PAWN | SquareContent::KING => return None,
+            figure => Some(figure)
+        },
+        None => None
+    };
+    return Some((from, to, promotion))
+}
+
+impl Board {
+    pub fn parse_uci_move(&self, uci: &str) -> Option<Move> {
+        // The legal move of the side to move the UCI string describes
+        let (from, to, promotion) = parse_uci(uci)?;
+        return self.legal_moves().into_iter().find(|mv| mv.from == from && mv.to == to && mv.promotion == promotion)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bitboard::{parse_square, square_name};
+    use crate::board::{Board, SquareContent};
+
+    #[test]
+    fn square_names() {
+        assert_eq!(square_name((0, 0)), "a8");
+        assert_eq!(square_name((7, 7)), "h1");
+        assert_eq!(square_name((4, 6)), "e2");
+        assert_eq!(parse_square("e2"), Some((4, 6)));
+        assert_eq!(parse_square("h8"), Some((7, 0)));
+        for name in ["i1", "a0", "a9", "e", "e22", ""].iter() {
+            assert_eq!(parse_square(name), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn moves() {
+        let board = Board::from_fen("r3k3/1P6/8/8/8/8/4P3/4K2R w Kq - 0 1").unwrap();
+        let push = board.parse_uci_move("e2e4").unwrap();
+        assert!(push.is_double_pawn_push());
+        assert_eq!(push.to_uci(), "e2e4");
+
+        let promotion = board.parse_uci_move("b7a8n").unwrap();
+        assert!(promotion.is_capture());
+        assert_eq!(promotion.promotion, Some(SquareContent::KNIGHT));
+        assert_eq!(promotion.to_uci(), "b7a8n");
+
+        let castling = board.parse_uci_move("e1g1").unwrap();
+        assert!(castling.is_castling());
+        assert_eq!(castling.to_uci(), "e1g1");
+
+        for uci in board.legal_moves().iter().map(|mv| mv.to_uci()) {
+            assert_eq!(board.parse_uci_move(&uci).unwrap().to_uci(), uci);
+        }
+    }
+
+    #[test]
+    fn illegal_moves() {
+        let board = Board::from_fen("r3k3/1P6/8/8/8/8/4P3/4K2R w Kq - 0 1").unwrap();
+        // Promotions need the figure, and nothing else can be promoted
+        for uci in ["b7b8", "b7b8k", "e2e3q", "e2e5", "e1c1", "a8a7", "e2", "e2e4x", "é2e4"].iter() {
+            assert_eq!(board.parse_uci_move(uci), None, "{}", uci);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::bitboard::{Bitboard, COLS, bit};
+use crate::board::{GameState, PlayerName};
+
+// Zobrist keys: a random number for every figure on every square, and for
+// every other part of the position which decides the moves available in it.
+// The key of a position is all the numbers which apply to it xored together,
+// so it can be kept up to date while moves are made by xoring out what
+// changes and xoring in what it changes into
+pub const PIECES: [[[u64; 64]; 6]; 2] = piece_keys();
+pub const BLACK_TO_MOVE: u64 = random(0);
+
+// Indexed by `CastlingRights::bits`, and by the file of the en passant square
+pub const CASTLING: [u64; 16] = random_keys::<16>(2 * 6 * 64 + 1);
+pub const EN_PASSANT: [u64; 8] = random_keys::<8>(2 * 6 * 64 + 1 + 16);
+
+pub fn state_key(state: &GameState, pawns: Bitboard) -> u64 {
+    // The part of a position's key which doesn't come from the figures, given
+    // the pawns of the side to move
+    let mut key = CASTLING[state.castling_rights.bits()];
+    if state.side_to_move == PlayerName::BLACK {
+        key ^= BLACK_TO_MOVE;
+    }
+
+    // The en passant square only changes the moves available when a pawn
+    // stands beside the one which just skipped it, and could take it.
+    // Otherwise the position is the same as if the pawn had come there in
+    // two moves, and gets the same key
+    if let Some((x, y)) = state.en_passant {
+        let pushed_y = if state.side_to_move == PlayerName::WHITE { y + 1 } else { y - 1 };
+        let beside = [x.wrapping_sub(1), x + 1].iter()
+            .filter(|&&x| x < COLS)
+            .fold(0, |beside, &x| beside | bit((x, pushed_y)));
+        if pawns & beside != 0 {
+            key ^= EN_PASSANT[x];
+        }
+    }
+    return key
+}
+
+const fn random(index: u64) -> u64 {
+    // The index-th number of the splitmix64 sequence, which is good enough
+    // for this and can be worked out at compile time
+    let mut z = (index + 1).wrapping_mul(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    return z ^ (z >> 31)
+}
+
+const fn random_keys<const N: usize>(first: u64) -> [u64; N] {
+    let mut keys = [0; N];
+    let mut i = 0;
+    while i < N {
+        keys[i] = random(first + i as u64);
+        i += 1;
+    }
+    return keys
+}
+
+const fn piece_keys() -> [[[u64; 64]; 6]; 2] {
+    let mut keys = [[[0; 64]; 6]; 2];
+    let mut player = 0;
+    while player < 2 {
+        let mut figure = 0;
+        while figure < 6 {
+            keys[player][figure] = random_keys::<64>(1 + ((player * 6 + figure) * 64) as u64);
+            figure += 1;
+        }
+        player += 1;
+    }
+    return keys
+}
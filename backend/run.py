@@ -6,6 +6,47 @@ app = Flask(__name__)
 app.secret_key = 'b409cfe2f5bb8af9898ac423ca728840934c5f65feb0e979778159aed78b2b81'
 CORS(app, resources={r'/api/*': {'origins': 'http://localhost:8080'}})
 
+# How long the bot may think about its move, in milliseconds
+MOVE_TIME_MS = 1000
+
+START_POSITION = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+
+def figures(board):
+    """
+    Maps the occupied squares of a board description to their figures.
+    """
+    return {
+        (int(square["x"]), int(square["y"])): (square["figure"], square["belongs_to"])
+        for square in board
+        if square["figure"] != "EMPTY"
+    }
+
+def played_position(board):
+    """
+    Finds the move the player made to reach the board, from the position the
+    bot left them in, or from the starting position when a new game began.
+    Describes the position it led to, with the whole game state the frontend
+    doesn't keep track of: castling rights, the en passant square and clocks.
+    """
+    # The history kept in the session ends with the position the bot left
+    # the player in, which is the one the move is made from
+    candidates = []
+    if "fen" in session:
+        candidates.append((session["fen"], session.get("position_history", [])[:-1]))
+    candidates.append((START_POSITION, []))
+
+    for fen, position_history in candidates:
+        try:
+            previous = plakychess.board_from_fen(fen)
+        except ValueError:
+            continue
+        for move in plakychess.get_available_moves(previous["board"], state=previous["state"]):
+            position = plakychess.make_move(
+                previous["board"], move, state=previous["state"], position_history=position_history)
+            if figures(position["board"]) == figures(board):
+                return position
+    return None
+
 @app.route("/api", methods=["GET", "POST"])
 @cross_origin(supports_credentials=True)
 def api_handler():
@@ -23,7 +64,48 @@ def api_handler():
         return jsonify({"hello": "world"})
     if request.method == "POST":
         game_state = request.get_json()
-        return jsonify(plakychess.get_next_move(game_state["board"]))
+
+        # The way the bot picks its moves is optional, and left to the
+        # defaults of the Rust library when missing. It has to answer in time,
+        # so its search always gets a time budget, which the caller can
+        # shorten but not extend
+        limits = {key: game_state[key] for key in ("strategy", "depth", "nodes") if key in game_state}
+        limits["time_ms"] = min(game_state.get("time_ms") or MOVE_TIME_MS, MOVE_TIME_MS)
+
+        # The frontend only sends the board, so the rest of the game state
+        # comes from the move the player made, played on the position the bot
+        # left them in, which is kept in the session along with the positions
+        # that could still be repeated. Their keys don't survive a round trip
+        # through JavaScript numbers
+        # A position the library can't make sense of is the caller's mistake
+        try:
+            position = played_position(game_state["board"])
+        except ValueError as error:
+            return jsonify({"error": str(error)}), 400
+        if position is not None:
+            state = position["state"]
+            position_history = position["position_history"][:-1]
+        else:
+            # A board no move leads to starts a new game from it, with black
+            # to move, since the frontend plays white
+            state = {"side_to_move": "BLACK"}
+            position_history = []
+
+        # Whatever state the caller does send takes precedence
+        state.update({
+            key: game_state[key]
+            for key in ("side_to_move", "castling_rights", "en_passant", "halfmove_clock", "fullmove_number")
+            if key in game_state
+        })
+
+        try:
+            response = plakychess.get_next_move(
+                game_state["board"], state=state, position_history=position_history, **limits)
+        except ValueError as error:
+            return jsonify({"error": str(error)}), 400
+        session["position_history"] = response.pop("position_history")
+        session["fen"] = plakychess.board_to_fen(response["board"], state=response["state"])
+        return jsonify(response)
 
 if __name__ == "__main__":
     app.run(host="0.0.0.0", port="5001", debug=True)